@@ -0,0 +1,23 @@
+
+# Completes services and run recipes of the current project, everything else is left to the generated function
+_dev-cli_dynamic() {
+    local cur prev kind
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "${prev}" in
+        -s|--service)
+            kind=services
+            ;;
+        run)
+            kind=recipes
+            ;;
+    esac
+
+    if [[ -n "${kind}" ]]; then
+        COMPREPLY=( $(compgen -W "$(dev-cli complete-values ${kind} 2>/dev/null)" -- "${cur}") )
+        return 0
+    fi
+    _dev-cli "$@"
+}
+
+complete -F _dev-cli_dynamic -o bashdefault -o default dev-cli
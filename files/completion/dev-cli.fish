@@ -0,0 +1,5 @@
+
+# Services and run recipes of the current project
+complete -c dev-cli -s s -l service -r -f -a "(dev-cli complete-values services 2>/dev/null)"
+complete -c dev-cli -n "__fish_seen_subcommand_from exec shell" -s s -l service -r -f -a "(dev-cli complete-values services 2>/dev/null)"
+complete -c dev-cli -n "__fish_seen_subcommand_from run" -f -a "(dev-cli complete-values recipes 2>/dev/null)"
@@ -0,0 +1,11 @@
+
+    # Services and run recipes of the current project
+    $dynamicWords = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+    $previous = if ($wordToComplete) { $dynamicWords[-2] } else { $dynamicWords[-1] }
+    $kind = if ($previous -in '-s', '--service') { 'services' } elseif ($previous -eq 'run') { 'recipes' }
+    if ($kind) {
+        & dev-cli complete-values $kind 2>$null | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+            [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_)
+        }
+        return
+    }
@@ -0,0 +1,115 @@
+use std::path::Path;
+use chrono::Local;
+use crate::utils::certs;
+use crate::utils::docker_compose::DockerCompose;
+use crate::utils::general::{get_app_config, get_project_root, print_table, CertCommand, OutputFormat};
+
+pub fn run(command: CertCommand) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        CertCommand::Trust => trust(),
+        CertCommand::Renew { all } => {
+            if all {
+                let certificates = certs::list()?;
+                if certificates.is_empty() {
+                    println!("There are no certificates to renew");
+                }
+                for certificate in certificates {
+                    certs::issue(&certificate.name, &certificate.hosts)?;
+                }
+                return Ok(());
+            }
+
+            let project_root = get_project_root()?;
+            let app_config = get_app_config(&project_root)?;
+            let config = DockerCompose::new(project_root.join("compose.yml"))
+                .with_config_from_docker(app_config.compose_config_from_docker.unwrap_or(false))
+                .config()?;
+            let name = config.name.clone().unwrap_or_default();
+            certs::issue(&name, &certs::project_hosts(&project_root, &config))?;
+            Ok(())
+        }
+        CertCommand::List { format } => {
+            let certificates = certs::list()?;
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&certificates)?),
+                OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&certificates)?),
+                OutputFormat::Text => {
+                    if certificates.is_empty() {
+                        println!("No certificates found, they are issued when a project is started");
+                        return Ok(());
+                    }
+                    let rows: Vec<Vec<String>> = certificates
+                        .iter()
+                        .map(|certificate| {
+                            let status = if !certificate.issued_by_ca {
+                                "not issued by the current CA"
+                            } else if certificate.expires_soon() {
+                                "expires soon"
+                            } else {
+                                "ok"
+                            };
+                            vec![
+                                certificate.name.clone(),
+                                certificate.hosts.join(", "),
+                                certificate.expires_at.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+                                status.to_string(),
+                            ]
+                        })
+                        .collect();
+                    print_table(&["NAME", "HOSTS", "EXPIRES", "STATUS"], &rows);
+                    println!("\nCA: {}", certs::ca_certificate_path().display());
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Adds the local CA to the trust store of the system, which asks for the password of the user
+fn trust() -> Result<(), Box<dyn std::error::Error>> {
+    // Issuing the default certificate creates the CA if there is none yet
+    certs::ensure_default_certificate()?;
+    let ca = certs::ca_certificate_path();
+
+    let commands: Vec<Vec<String>> = if cfg!(target_os = "macos") {
+        vec![command(&["sudo", "security", "add-trusted-cert", "-d", "-r", "trustRoot", "-k", "/Library/Keychains/System.keychain"], &ca)]
+    } else if cfg!(windows) {
+        vec![command(&["certutil", "-user", "-addstore", "Root"], &ca)]
+    } else {
+        // Debian, Ubuntu and Alpine, then Fedora and RHEL, then Arch
+        let stores = [
+            ("/usr/local/share/ca-certificates", "update-ca-certificates"),
+            ("/etc/pki/ca-trust/source/anchors", "update-ca-trust"),
+            ("/etc/ca-certificates/trust-source/anchors", "trust extract-compat"),
+        ];
+        let (dir, update) = stores
+            .iter()
+            .find(|(dir, _)| Path::new(dir).is_dir())
+            .ok_or_else(|| format!("Could not find the trust store of the system, add {} to it by hand", ca.display()))?;
+        let target = Path::new(dir).join("dev-cli.crt");
+        let mut update: Vec<String> = update.split(' ').map(String::from).collect();
+        update.insert(0, String::from("sudo"));
+        vec![
+            vec![String::from("sudo"), String::from("cp"), ca.display().to_string(), target.display().to_string()],
+            update,
+        ]
+    };
+
+    for command in commands {
+        println!("Running {}", command.join(" "));
+        let status = subprocess::Exec::cmd(&command[0]).args(&command[1..]).join()?;
+        if !status.success() {
+            return Err(format!("Could not add {} to the trust store of the system", ca.display()).into());
+        }
+    }
+
+    println!("The local CA is trusted now");
+    println!("Firefox uses its own trust store, import {} in its certificate settings", ca.display());
+    Ok(())
+}
+
+fn command(args: &[&str], ca: &Path) -> Vec<String> {
+    let mut command: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    command.push(ca.display().to_string());
+    command
+}
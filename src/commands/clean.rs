@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use bollard::container::{ListContainersOptions, RemoveContainerOptions};
+use bollard::network::InspectNetworkOptions;
+use bollard::models::Volume;
+use bollard::volume::ListVolumesOptions;
+use bollard::Docker;
+use crate::DATA_DIR;
+use crate::utils::general::{confirm, format_bytes, print_table, CleanItem, DEV_CLI_NETWORK};
+use crate::utils::project_registry::{ProjectRegistry, RegisteredProject};
+use crate::utils::{addons, certs, proxy};
+
+/// Something dev-cli created, which `clean` can remove
+struct Artefact {
+    item: CleanItem,
+    kind: Kind,
+    /// Unknown for networks and for volumes of drivers that don't report it
+    size: Option<u64>,
+}
+
+enum Kind {
+    Network(String),
+    Volume(String),
+    Directory(PathBuf),
+}
+
+impl Artefact {
+    fn name(&self) -> String {
+        match &self.kind {
+            Kind::Network(name) => name.clone(),
+            Kind::Volume(name) => format!("volume {}", name),
+            Kind::Directory(path) => path.display().to_string(),
+        }
+    }
+}
+
+pub async fn run(docker: &Docker, items: Vec<CleanItem>, all: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Only the files are listed if Docker is not running
+    let docker = match docker.ping().await {
+        Ok(_) => Some(docker),
+        Err(_) => {
+            eprintln!("Docker is not running, only the files of dev-cli are listed");
+            None
+        }
+    };
+
+    let artefacts = artefacts(docker).await?;
+    if artefacts.is_empty() {
+        println!("Nothing to clean");
+        return Ok(());
+    }
+
+    let selected: Vec<&Artefact> = artefacts
+        .iter()
+        .filter(|artefact| all || items.contains(&artefact.item))
+        .collect();
+    let listed = if selected.is_empty() { artefacts.iter().collect() } else { selected.clone() };
+
+    let rows: Vec<Vec<String>> = listed
+        .iter()
+        .map(|artefact| vec![
+            artefact.item.to_string(),
+            artefact.name(),
+            artefact.size.map(format_bytes).unwrap_or_default(),
+        ])
+        .collect();
+    print_table(&["ITEM", "NAME", "SIZE"], &rows);
+    let total: u64 = listed.iter().filter_map(|artefact| artefact.size).sum();
+    println!("\nTotal: {}", format_bytes(total));
+
+    // Without a selection this is a dry run
+    if !all && items.is_empty() {
+        println!("\nRun `dev-cli clean --all` or `dev-cli clean <ITEM>...` to remove them");
+        return Ok(());
+    }
+    if selected.is_empty() {
+        println!("\nNothing to clean for the selected items");
+        return Ok(());
+    }
+    if !yes && !confirm("\nRemove these items?")? {
+        println!("Nothing was removed");
+        return Ok(());
+    }
+
+    // The proxy containers use the network and the mounted directories, so they go first
+    if let Some(docker) = docker {
+        if selected.iter().any(|artefact| matches!(artefact.item, CleanItem::Proxy | CleanItem::Network)) {
+            remove_proxy_containers(docker).await?;
+        }
+    }
+
+    let mut failed = 0;
+    for artefact in selected {
+        let result: Result<(), Box<dyn std::error::Error>> = match (&artefact.kind, docker) {
+            (Kind::Network(name), Some(docker)) => docker.remove_network(name).await.map_err(Into::into),
+            (Kind::Volume(name), Some(docker)) => docker.remove_volume(name, None).await.map_err(Into::into),
+            (Kind::Directory(path), _) => std::fs::remove_dir_all(path).map_err(Into::into),
+            (_, None) => continue,
+        };
+        match result {
+            Ok(()) => println!("Removed {}", artefact.name()),
+            Err(error) => {
+                eprintln!("Could not remove {} ({})", artefact.name(), error);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} item(s) could not be removed", failed).into());
+    }
+    Ok(())
+}
+
+/// Everything dev-cli created on this machine, in the order it has to be removed
+async fn artefacts(docker: Option<&Docker>) -> Result<Vec<Artefact>, Box<dyn std::error::Error>> {
+    let mut artefacts = vec![];
+    let directory = |item, path: PathBuf| {
+        path.is_dir().then(|| Artefact { item, size: Some(directory_size(&path)), kind: Kind::Directory(path) })
+    };
+
+    artefacts.extend(directory(CleanItem::Proxy, proxy::proxy_dir()));
+    artefacts.extend(directory(CleanItem::Certs, certs::ca_dir()));
+    artefacts.extend(directory(CleanItem::Certs, DATA_DIR.join("certs")));
+    artefacts.extend(directory(CleanItem::Certs, DATA_DIR.join("certs-conf")));
+    artefacts.extend(directory(CleanItem::Dns, DATA_DIR.join("dns")));
+    artefacts.extend(directory(CleanItem::Addons, addons::override_dir()));
+
+    let Some(docker) = docker else {
+        return Ok(artefacts);
+    };
+
+    // Unused volumes of compose projects, only those of deleted dev-cli projects are removed
+    let volumes = docker.list_volumes(Some(ListVolumesOptions {
+        filters: HashMap::from([
+            ("dangling", vec!["true"]),
+            ("label", vec!["com.docker.compose.project"]),
+        ]),
+    })).await?;
+    // Only `docker system df` knows the size of volumes
+    let sizes: HashMap<String, i64> = docker.df().await?
+        .volumes
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|volume| Some((volume.name, volume.usage_data?.size)))
+        .collect();
+    let registry = ProjectRegistry::load()?;
+    for name in orphaned_volumes(volumes.volumes.unwrap_or_default(), &registry.projects) {
+        artefacts.push(Artefact {
+            item: CleanItem::Volumes,
+            size: sizes.get(&name).and_then(|size| u64::try_from(*size).ok()),
+            kind: Kind::Volume(name),
+        });
+    }
+
+    // Projects still attached to the network keep it from being removed, so it comes last
+    if docker.inspect_network(DEV_CLI_NETWORK, None::<InspectNetworkOptions<String>>).await.is_ok() {
+        artefacts.push(Artefact {
+            item: CleanItem::Network,
+            kind: Kind::Network(String::from(DEV_CLI_NETWORK)),
+            size: None,
+        });
+    }
+    Ok(artefacts)
+}
+
+/// The volumes of registered projects whose root directory is gone. Volumes of projects which
+/// still exist, even if they are stopped, and of compose projects dev-cli doesn't know are kept.
+fn orphaned_volumes(volumes: Vec<Volume>, projects: &[RegisteredProject]) -> Vec<String> {
+    let existing: HashSet<&str> = projects
+        .iter()
+        .filter(|project| project.root.exists())
+        .map(|project| project.name.as_str())
+        .collect();
+    let missing: HashSet<&str> = projects
+        .iter()
+        .filter(|project| !project.root.exists())
+        .map(|project| project.name.as_str())
+        .filter(|name| !existing.contains(name))
+        .collect();
+
+    let mut volumes: Vec<String> = volumes
+        .into_iter()
+        .filter(|volume| {
+            volume.labels
+                .get("com.docker.compose.project")
+                .is_some_and(|project| missing.contains(project.as_str()))
+        })
+        .map(|volume| volume.name)
+        .collect();
+    volumes.sort();
+    volumes
+}
+
+/// Removes the containers of the proxy stack (Traefik, DNS), they are recreated by the next `start`
+async fn remove_proxy_containers(docker: &Docker) -> Result<(), bollard::errors::Error> {
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        all: true,
+        filters: HashMap::from([(
+            String::from("label"),
+            vec![format!("com.docker.compose.project={}", proxy::project_name())],
+        )]),
+        ..Default::default()
+    })).await?;
+
+    for id in containers.into_iter().filter_map(|container| container.id) {
+        docker.remove_container(&id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await?;
+    }
+    Ok(())
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => entry.metadata().map_or(0, |metadata| metadata.len()),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn select_volumes_of_deleted_projects_only() -> Result<(), Box<dyn std::error::Error>> {
+    let existing_root = assert_fs::TempDir::new()?;
+    let project = |name: &str, root: &Path| RegisteredProject {
+        root: root.to_path_buf(),
+        name: name.to_string(),
+        last_used: chrono::Utc::now(),
+    };
+    let projects = [
+        project("shop", existing_root.path()),
+        project("blog", &existing_root.path().join("deleted")),
+        // Registered twice, once with a root that still exists
+        project("wiki", &existing_root.path().join("moved")),
+        project("wiki", existing_root.path()),
+    ];
+    let volume = |name: &str, project: Option<&str>| Volume {
+        name: name.to_string(),
+        labels: project
+            .map(|project| HashMap::from([(String::from("com.docker.compose.project"), project.to_string())]))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let volumes = vec![
+        volume("shop_db", Some("shop")),
+        volume("blog_uploads", Some("blog")),
+        volume("blog_db", Some("blog")),
+        volume("wiki_db", Some("wiki")),
+        volume("other_db", Some("other")),
+        volume("loose", None),
+    ];
+
+    assert_eq!(orphaned_volumes(volumes, &projects), ["blog_db", "blog_uploads"]);
+    Ok(())
+}
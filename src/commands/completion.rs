@@ -0,0 +1,70 @@
+use clap::CommandFactory;
+use clap_complete::Shell;
+use crate::utils::docker_compose::DockerCompose;
+use crate::utils::general::{find_project_root, get_app_config, Asset, Cli, CompletionValues};
+
+pub fn run(shell: Shell) -> Result<(), Box<dyn std::error::Error>> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    let mut script = vec![];
+    clap_complete::generate(shell, &mut command, &name, &mut script);
+    let script = String::from_utf8(script)?;
+
+    // The generated scripts only know the static arguments, the dynamic ones ask `dev-cli complete-values`
+    let script = match shell {
+        Shell::Bash => script + &dynamic_script("dev-cli.bash")?,
+        Shell::Fish => script + &dynamic_script("dev-cli.fish")?,
+        Shell::Zsh => {
+            let generated = script.replacen("\n_dev-cli() {", "\n_dev-cli_generated() {", 1);
+            let trailer = "if [ \"$funcstack[1]\" = \"_dev-cli\" ]; then";
+            match generated.rfind(trailer) {
+                Some(index) => format!("{}{}{}", &generated[..index], dynamic_script("dev-cli.zsh")?, &generated[index..]),
+                None => script,
+            }
+        }
+        Shell::PowerShell => {
+            let parameters = "param($wordToComplete, $commandAst, $cursorPosition)\n";
+            match script.find(parameters) {
+                Some(index) => {
+                    let index = index + parameters.len();
+                    format!("{}{}{}", &script[..index], dynamic_script("dev-cli.ps1")?, &script[index..])
+                }
+                None => script,
+            }
+        }
+        _ => script,
+    };
+
+    print!("{}", script);
+    Ok(())
+}
+
+/// Prints the services or run recipes of the current project for the completion scripts.
+/// Outside of a project or with a broken config there is simply nothing to complete.
+pub fn values(kind: CompletionValues) {
+    let Ok(Some(project_root)) = find_project_root() else {
+        return;
+    };
+    let Ok(app_config) = get_app_config(&project_root) else {
+        return;
+    };
+
+    let values: Vec<String> = match kind {
+        CompletionValues::Services => DockerCompose::new(project_root.join("compose.yml"))
+            .with_config_from_docker(app_config.compose_config_from_docker.unwrap_or(false))
+            .config()
+            .map(|config| config.services.into_keys().collect())
+            .unwrap_or_default(),
+        CompletionValues::Recipes => app_config.run_commands
+            .map(|run_commands| run_commands.into_keys().collect())
+            .unwrap_or_default(),
+    };
+    for value in values {
+        println!("{}", value);
+    }
+}
+
+fn dynamic_script(file: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let asset = Asset::get(&format!("completion/{}", file)).ok_or("Missing completion script")?;
+    Ok(String::from_utf8(asset.data.into_owned())?)
+}
@@ -0,0 +1,161 @@
+use std::path::Path;
+use serde_yaml::Value;
+use crate::utils::app_config::{config_files, parse_with_unknown_keys, AppConfig, ConfigLayer};
+use crate::utils::general::{find_project_root, get_app_config, print_table, ConfigCommand};
+use crate::utils::yaml_edit;
+
+pub fn run(command: ConfigCommand) -> Result<(), Box<dyn std::error::Error>> {
+    // Only `--global` works outside of a project
+    let project_root = find_project_root()?;
+
+    match command {
+        ConfigCommand::Show => {
+            let project_root = project_root.ok_or("Could not find a project root")?;
+            let merged = serde_yaml::to_value(get_app_config(&project_root)?)?;
+
+            let mut layers = vec![(ConfigLayer::Default, serde_yaml::to_value(AppConfig::default())?)];
+            for (layer, file) in config_files(Some(&project_root)) {
+                if file.is_file() {
+                    layers.push((layer, serde_yaml::from_str(&std::fs::read_to_string(&file)?)?));
+                }
+            }
+
+            let rows: Vec<Vec<String>> = leaves(&merged)
+                .into_iter()
+                .map(|(key, value)| {
+                    let path: Vec<&str> = key.split('.').collect();
+                    let layer = layers
+                        .iter()
+                        .rev()
+                        .find(|(_, layer)| lookup(layer, &path).is_some_and(|value| !value.is_null()))
+                        .map_or(ConfigLayer::Default, |(layer, _)| *layer);
+                    vec![key, format_value(&value), layer.to_string()]
+                })
+                .collect();
+            print_table(&["KEY", "VALUE", "SOURCE"], &rows);
+        }
+        ConfigCommand::Get { key } => {
+            let project_root = project_root.ok_or("Could not find a project root")?;
+            let merged = serde_yaml::to_value(get_app_config(&project_root)?)?;
+            let path: Vec<&str> = key.split('.').collect();
+
+            match lookup(&merged, &path) {
+                Some(Value::Null) | None => return Err(format!("The config key '{}' is not set", key).into()),
+                Some(value @ (Value::Mapping(_) | Value::Sequence(_))) => print!("{}", serde_yaml::to_string(value)?),
+                Some(value) => println!("{}", format_value(value)),
+            }
+        }
+        ConfigCommand::Set { key, value, global, project, local: _ } => {
+            let file = if global {
+                config_files(None).remove(0).1
+            } else {
+                let project_root = match project_root {
+                    Some(project_root) => project_root.to_path_buf(),
+                    None => std::env::current_dir()?,
+                };
+                let layer = if project { ConfigLayer::Dist } else { ConfigLayer::Local };
+                config_files(Some(&project_root))
+                    .into_iter()
+                    .find(|(file_layer, _)| *file_layer == layer)
+                    .unwrap()
+                    .1
+            };
+
+            let content = set(&file, &key, &value)?;
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, content)?;
+            println!("Set {} in {}", key, file.display());
+        }
+        ConfigCommand::Validate => {
+            let mut problems = 0;
+            for (layer, file) in config_files(project_root.as_deref()) {
+                if !file.is_file() {
+                    continue;
+                }
+                match parse_with_unknown_keys(&std::fs::read_to_string(&file)?) {
+                    Ok((_, unknown_keys)) if unknown_keys.is_empty() => {
+                        println!("{} ({}): ok", file.display(), layer);
+                    }
+                    Ok((_, unknown_keys)) => {
+                        for key in unknown_keys {
+                            println!("{} ({}): unknown key '{}'", file.display(), layer, key);
+                            problems += 1;
+                        }
+                    }
+                    Err(error) => {
+                        println!("{} ({}): {:#}", file.display(), layer, error);
+                        problems += 1;
+                    }
+                }
+            }
+            if problems > 0 {
+                return Err(format!("Found {} problem(s) in the config", problems).into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the new content of the config file, refusing unknown keys and values of the wrong type
+fn set(file: &Path, key: &str, value: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let content = if file.is_file() { std::fs::read_to_string(file)? } else { String::new() };
+
+    // `true` or `3` are set as a boolean or number, everything else as it's written
+    let value: Value = serde_yaml::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    let rendered = match &value {
+        Value::Mapping(_) | Value::Sequence(_) => serde_json::to_string(&value)?,
+        _ => {
+            let rendered = serde_yaml::to_string(&value)?.trim_end().to_string();
+            if rendered.contains('\n') { serde_json::to_string(&value)? } else { rendered }
+        }
+    };
+
+    let path: Vec<&str> = key.split('.').collect();
+    let content = yaml_edit::set_value(&content, &path, &rendered)?;
+
+    let (_, unknown_keys) = parse_with_unknown_keys(&content)
+        .map_err(|error| format!("Invalid value for '{}': {:#}", key, error))?;
+    if unknown_keys.iter().any(|unknown| unknown == key || key.starts_with(&format!("{}.", unknown))) {
+        return Err(format!("Unknown config key '{}'", key).into());
+    }
+    Ok(content)
+}
+
+/// Flattens a value into `a.b.c` keys, lists are kept as a single value
+fn leaves(value: &Value) -> Vec<(String, Value)> {
+    match value {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .flat_map(|(key, value)| {
+                let key = format_value(key);
+                let children = leaves(value);
+                if children.is_empty() && !value.is_mapping() {
+                    vec![(key, value.clone())]
+                } else {
+                    children
+                        .into_iter()
+                        .map(|(child, value)| (format!("{}.{}", key, child), value))
+                        .collect()
+                }
+            })
+            .filter(|(_, value)| !value.is_null())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(*key))
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        Value::Bool(bool) => bool.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Null => String::new(),
+        _ => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::Path;
+use chrono::Local;
+use crate::commands::snapshot::create_snapshot;
+use crate::utils::addons;
+use crate::utils::dns::{self, HOSTS_FILE};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::confirm;
+use crate::utils::project_registry::ProjectRegistry;
+use crate::utils::proxy;
+
+pub fn run(docker_compose: DockerCompose, config: &Config, project_root: &Path, yes: bool, snapshot: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let name = config.name.clone().unwrap_or_default();
+
+    let question = format!(
+        "Delete the project '{}' including its database, volumes and locally built images?",
+        name
+    );
+    if !yes && !confirm(&question)? {
+        println!("Nothing was deleted");
+        return Ok(());
+    }
+
+    // Snapshots are stored in the project directory, so they survive the deletion
+    if snapshot {
+        create_snapshot(project_root, &format!("before-delete-{}", Local::now().format("%Y%m%d-%H%M%S")))?;
+    }
+
+    println!("Removing the containers, volumes and images of '{}' ...", name);
+    docker_compose.remove()?;
+
+    for file in proxy::project_files(&name) {
+        std::fs::remove_file(&file)?;
+        println!("Removed {}", file.display());
+    }
+
+    let addons_file = addons::override_file(project_root);
+    if addons_file.is_file() {
+        std::fs::remove_file(&addons_file)?;
+        println!("Removed {}", addons_file.display());
+    }
+
+    // Written by `hostname` if the DNS container is disabled, it may have been enabled since
+    if dns::remove_hosts_block(Path::new(HOSTS_FILE), &name)? {
+        println!("Removed the hostnames of '{}' from {}", name, HOSTS_FILE);
+    }
+
+    let mut registry = ProjectRegistry::load()?;
+    if registry.remove(project_root) {
+        registry.save()?;
+    }
+
+    println!("Deleted the project '{}'", name);
+    Ok(())
+}
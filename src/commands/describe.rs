@@ -0,0 +1,237 @@
+use std::path::Path;
+use bollard::Docker;
+use serde::Serialize;
+use crate::commands::status::{service_statuses, ServiceStatus};
+use crate::utils::app_config::{config_files, AppConfig};
+use crate::utils::database::Engine;
+use crate::utils::docker_compose::{Config, ServicePort, ServiceVolume};
+use crate::utils::general::OutputFormat;
+use crate::utils::traefik::{self, Router};
+
+#[derive(Debug, Serialize)]
+struct ProjectDescription {
+    name: String,
+    root: String,
+    config_files: Vec<ConfigFileDescription>,
+    config: AppConfig,
+    services: Vec<ServiceDescription>,
+    database: Option<DatabaseDescription>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigFileDescription {
+    layer: String,
+    path: String,
+    found: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ServiceDescription {
+    name: String,
+    image: Option<String>,
+    /// The build context if the image is built locally
+    build: Option<String>,
+    ports: Vec<String>,
+    volumes: Vec<String>,
+    networks: Vec<String>,
+    routes: Vec<Router>,
+    urls: Vec<String>,
+    containers: Vec<ServiceStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct DatabaseDescription {
+    service: String,
+    engine: Engine,
+    /// How other services of the project reach the database
+    host: String,
+    port: u16,
+    /// The address on the host, if the port is published
+    published: Option<String>,
+    database: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+pub async fn run(
+    docker: &Docker,
+    project_root: &Path,
+    app_config: &AppConfig,
+    config: &Config,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = config.name.clone().unwrap_or_default();
+    let mut statuses = service_statuses(docker, &name, Some(config)).await?;
+
+    let services = config.services
+        .iter()
+        .map(|(service_name, service)| {
+            let routes = traefik::routers(&service.labels);
+            let urls = routes
+                .iter()
+                .flat_map(|router| {
+                    let scheme = if router.tls { "https" } else { "http" };
+                    router.hosts.iter().map(move |host| format!("{}://{}", scheme, host))
+                })
+                .collect();
+            let (containers, others) = statuses.drain(..).partition(|status| &status.service == service_name);
+            statuses = others;
+
+            ServiceDescription {
+                name: service_name.clone(),
+                image: service.image.clone(),
+                build: service.build.as_ref().map(|build| build.context.clone()),
+                ports: service.ports.iter().map(format_port).collect(),
+                volumes: service.volumes.iter().map(format_volume).collect(),
+                networks: service.networks.keys().cloned().collect(),
+                routes,
+                urls,
+                containers,
+            }
+        })
+        .collect();
+
+    let description = ProjectDescription {
+        name,
+        root: project_root.display().to_string(),
+        config_files: config_files(Some(project_root))
+            .into_iter()
+            .map(|(layer, path)| ConfigFileDescription {
+                layer: layer.to_string(),
+                found: path.is_file(),
+                path: path.display().to_string(),
+            })
+            .collect(),
+        config: app_config.clone(),
+        services,
+        database: describe_database(app_config, config),
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&description)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&description)?),
+        OutputFormat::Text => print_text(&description)?,
+    }
+    Ok(())
+}
+
+fn describe_database(app_config: &AppConfig, config: &Config) -> Option<DatabaseDescription> {
+    let service_name = app_config.database_container.clone().unwrap_or(String::from("db"));
+    let engine = Engine::detect(config, &service_name).ok()?;
+    let service = &config.services[&service_name];
+    let variable = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| service.environment.get(*name).cloned().flatten())
+    };
+
+    let (port, user, password) = match engine {
+        Engine::MySql => (
+            3306,
+            variable(&["MYSQL_USER", "MARIADB_USER"]).or(Some(String::from("root"))),
+            variable(&["MYSQL_PASSWORD", "MARIADB_PASSWORD", "MYSQL_ROOT_PASSWORD", "MARIADB_ROOT_PASSWORD"]),
+        ),
+        Engine::Postgres => (
+            5432,
+            variable(&["POSTGRES_USER"]).or(Some(String::from("postgres"))),
+            variable(&["POSTGRES_PASSWORD"]),
+        ),
+    };
+    let published = service.ports
+        .iter()
+        .find(|service_port| service_port.target == port)
+        .and_then(|service_port| {
+            let published = service_port.published.as_ref()?;
+            let host_ip = service_port.host_ip.as_deref().unwrap_or("127.0.0.1");
+            Some(format!("{}:{}", host_ip, published))
+        });
+
+    Some(DatabaseDescription {
+        database: engine.default_database(config, &service_name),
+        host: service_name.clone(),
+        service: service_name,
+        engine,
+        port,
+        published,
+        user,
+        password,
+    })
+}
+
+fn print_text(description: &ProjectDescription) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Project:  {}", description.name);
+    println!("Root:     {}", description.root);
+
+    println!("\nConfig files:");
+    for file in &description.config_files {
+        let found = if file.found { "" } else { " (not found)" };
+        println!("  {:<8}{}{}", file.layer, file.path, found);
+    }
+
+    println!("\nConfig:");
+    for line in serde_yaml::to_string(&description.config)?.lines() {
+        println!("  {}", line);
+    }
+
+    println!("\nServices:");
+    for service in &description.services {
+        let states: Vec<String> = service.containers
+            .iter()
+            .map(|container| match &container.health {
+                Some(health) => format!("{}, {}", container.state, health),
+                None => container.state.clone(),
+            })
+            .collect();
+        println!("  {} ({})", service.name, states.join("; "));
+
+        let image = match (&service.image, &service.build) {
+            (Some(image), _) => image.clone(),
+            (None, Some(context)) => format!("built from {}", context),
+            (None, None) => String::new(),
+        };
+        let fields = [
+            ("Image", image),
+            ("Ports", service.ports.join(", ")),
+            ("Volumes", service.volumes.join(", ")),
+            ("Networks", service.networks.join(", ")),
+            ("URLs", service.urls.join(", ")),
+        ];
+        for (label, value) in fields.iter().filter(|(_, value)| !value.is_empty()) {
+            println!("    {:<10}{}", format!("{}:", label), value);
+        }
+    }
+
+    if let Some(database) = &description.database {
+        println!("\nDatabase:");
+        let mut host = format!("{}:{} (from the services)", database.host, database.port);
+        if let Some(published) = &database.published {
+            host += &format!(", {} (from the host)", published);
+        }
+        let fields = [
+            ("Engine", Some(database.engine.to_string())),
+            ("Host", Some(host)),
+            ("Database", database.database.clone()),
+            ("User", database.user.clone()),
+            ("Password", database.password.clone()),
+        ];
+        for (label, value) in fields.iter().filter_map(|(label, value)| Some((label, value.as_ref()?))) {
+            println!("  {:<10}{}", format!("{}:", label), value);
+        }
+    }
+    Ok(())
+}
+
+fn format_port(port: &ServicePort) -> String {
+    let protocol = port.protocol.as_deref().unwrap_or("tcp");
+    match (&port.host_ip, &port.published) {
+        (Some(host_ip), Some(published)) => format!("{}:{}->{}/{}", host_ip, published, port.target, protocol),
+        (None, Some(published)) => format!("{}->{}/{}", published, port.target, protocol),
+        (_, None) => format!("{}/{}", port.target, protocol),
+    }
+}
+
+fn format_volume(volume: &ServiceVolume) -> String {
+    let source = volume.source.as_deref().unwrap_or("anonymous");
+    let read_only = if volume.read_only { ", read-only" } else { "" };
+    format!("{} -> {} ({}{})", source, volume.target, volume.volume_type, read_only)
+}
@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use crate::utils::app_config::AppConfig;
+use crate::utils::database::{Compression, Engine};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::format_bytes;
+
+pub fn run(
+    docker_compose: DockerCompose,
+    config: &Config,
+    app_config: &AppConfig,
+    project_root: &Path,
+    database: Option<String>,
+    output: Option<String>,
+    compress: Option<Compression>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service = app_config.database_container.clone().unwrap_or(String::from("db"));
+    let engine = Engine::detect(config, &service)?;
+
+    // `-` writes to stdout, so nothing else may be printed there
+    let path = match output {
+        Some(output) if output == "-" => None,
+        Some(output) => Some(PathBuf::from(output)),
+        None => {
+            let name = database.clone()
+                .or_else(|| engine.default_database(config, &service))
+                .or_else(|| config.name.clone())
+                .unwrap_or(String::from("dump"));
+            let file_name = format!(
+                "{}-{}.{}",
+                name,
+                Local::now().format("%Y%m%d-%H%M%S"),
+                compress.unwrap_or(Compression::None).extension()
+            );
+            let dumps_dir = app_config.dumps_dir.clone().unwrap_or(String::from("dumps"));
+            Some(project_root.join(dumps_dir).join(file_name))
+        }
+    };
+    let compression = match (compress, &path) {
+        (Some(compress), _) => compress,
+        (None, Some(path)) => Compression::from_path(path),
+        (None, None) => Compression::None,
+    };
+
+    dump(&docker_compose, &service, engine, database.as_deref(), path.as_deref(), compression)?;
+
+    if let Some(path) = path {
+        let size = std::fs::metadata(&path)?.len();
+        println!("Exported the database to {} ({})", path.display(), format_bytes(size));
+    }
+    Ok(())
+}
+
+/// Runs the dump tool of `engine` in the service and writes the dump to `path`, or to stdout without one
+pub fn dump(
+    docker_compose: &DockerCompose,
+    service: &str,
+    engine: Engine,
+    database: Option<&str>,
+    path: Option<&Path>,
+    compression: Compression,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut process = docker_compose
+        .exec_cmd(
+            service.to_string(),
+            None,
+            None,
+            vec![String::from("sh"), String::from("-c"), engine.dump_script(database)],
+            false,
+        )
+        .stdin(subprocess::NullFile)
+        .stdout(subprocess::Redirection::Pipe)
+        .popen()?;
+    let mut dump = process.stdout.take().unwrap();
+
+    let copied = match path {
+        Some(path) => path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| File::create(path))
+            .and_then(|file| compression.compress(&mut dump, BufWriter::new(file))),
+        None => compression.compress(&mut dump, std::io::stdout().lock()),
+    };
+
+    // Nobody reads the pipe anymore after an error, so the dump tool would block on it forever
+    drop(dump);
+    if copied.is_err() {
+        let _ = process.kill();
+    }
+    let status = process.wait()?;
+
+    if copied.is_err() || !status.success() {
+        // A partial dump would look like a valid one later on
+        if let Some(path) = path {
+            let _ = std::fs::remove_file(path);
+        }
+        copied?;
+        return Err(format!("Dumping the database in '{}' failed ({:?})", service, status).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use bollard::Docker;
+use serde::Serialize;
+use crate::commands::status::service_statuses;
+use crate::utils::general::{format_duration, print_table, OutputFormat};
+use crate::utils::project_registry::ProjectRegistry;
+
+#[derive(Debug, Serialize)]
+struct ProjectStatus {
+    name: String,
+    root: String,
+    /// `running`, `stopped` or `missing` if the root directory no longer exists
+    state: String,
+    running_containers: usize,
+    containers: usize,
+    last_used: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn run(docker: &Docker, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = ProjectRegistry::load()?;
+
+    let mut statuses = vec![];
+    for project in registry.projects {
+        let containers = service_statuses(docker, &project.name, None).await?;
+        let running_containers = containers
+            .iter()
+            .filter(|container| container.state == "running")
+            .count();
+        let state = if !project.root.is_dir() {
+            "missing"
+        } else if running_containers > 0 {
+            "running"
+        } else {
+            "stopped"
+        };
+
+        statuses.push(ProjectStatus {
+            name: project.name,
+            root: project.root.display().to_string(),
+            state: state.to_string(),
+            running_containers,
+            containers: containers.len(),
+            last_used: project.last_used,
+        });
+    }
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&statuses)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&statuses)?),
+        OutputFormat::Text => {
+            if statuses.is_empty() {
+                println!("No projects have been started with dev-cli yet");
+                return Ok(());
+            }
+
+            let rows: Vec<Vec<String>> = statuses
+                .iter()
+                .map(|status| vec![
+                    status.name.clone(),
+                    status.state.clone(),
+                    format!("{}/{}", status.running_containers, status.containers),
+                    format!("{} ago", format_duration(chrono::Utc::now().signed_duration_since(status.last_used))),
+                    status.root.clone(),
+                ])
+                .collect();
+            print_table(&["PROJECT", "STATE", "CONTAINERS", "LAST USED", "ROOT"], &rows);
+        }
+    }
+
+    Ok(())
+}
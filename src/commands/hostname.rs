@@ -0,0 +1,119 @@
+use std::path::Path;
+use bollard::Docker;
+use crate::utils::app_config::AppConfig;
+use crate::utils::dns::{self, HOSTS_FILE};
+use crate::utils::docker_compose::Config;
+use crate::utils::general::{print_table, HostnameCommand};
+use crate::utils::traefik;
+
+pub async fn run(docker: &Docker, app_config: &AppConfig, config: &Config, command: HostnameCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let project = config.name.clone().unwrap_or_default();
+    let use_dns = app_config.dns_container.unwrap_or(true);
+    let hosts_file = Path::new(HOSTS_FILE);
+
+    match command {
+        HostnameCommand::Add { hosts } => {
+            let hosts = if hosts.is_empty() { router_hosts(config) } else { hosts };
+            if hosts.is_empty() {
+                return Err("The project has no Traefik routers with a hostname, pass the hostnames to add".into());
+            }
+
+            let mut registered = registered_hosts(&project, use_dns);
+            let added: Vec<String> = hosts.into_iter().filter(|host| !registered.contains(host)).collect();
+            if added.is_empty() {
+                println!("All hostnames are registered already");
+                return Ok(());
+            }
+            registered.extend(added.iter().cloned());
+
+            save(docker, &project, use_dns, &registered).await?;
+            for host in added {
+                println!("Added {}", host);
+            }
+        }
+        HostnameCommand::Remove { hosts } => {
+            let registered = registered_hosts(&project, use_dns);
+            let (removed, kept): (Vec<String>, Vec<String>) = registered
+                .into_iter()
+                .partition(|host| hosts.is_empty() || hosts.contains(host));
+            if removed.is_empty() {
+                println!("None of the hostnames are registered");
+                return Ok(());
+            }
+
+            save(docker, &project, use_dns, &kept).await?;
+            for host in removed {
+                println!("Removed {}", host);
+            }
+        }
+        HostnameCommand::List => {
+            let in_dns = dns::read_snippet(&project);
+            let in_hosts_file = dns::hosts_block(&std::fs::read_to_string(hosts_file).unwrap_or_default(), &project);
+
+            let mut rows: Vec<Vec<String>> = vec![];
+            let mut add_row = |host: &String, service: &str| {
+                if rows.iter().any(|row| &row[0] == host) {
+                    return;
+                }
+                let mut registered = vec![];
+                if in_dns.contains(host) {
+                    registered.push(String::from("dns"));
+                }
+                if in_hosts_file.contains(host) {
+                    registered.push(hosts_file.display().to_string());
+                }
+                rows.push(vec![host.clone(), service.to_string(), registered.join(", ")]);
+            };
+            for (service, routers) in traefik::service_routers(config) {
+                for host in routers.iter().flat_map(|router| &router.hosts) {
+                    add_row(host, &service);
+                }
+            }
+            for host in in_dns.iter().chain(&in_hosts_file) {
+                add_row(host, "");
+            }
+
+            if rows.is_empty() {
+                println!("The project has no hostnames");
+                return Ok(());
+            }
+            print_table(&["HOST", "SERVICE", "REGISTERED IN"], &rows);
+        }
+    }
+    Ok(())
+}
+
+/// The hostnames of the Traefik routers of all services
+fn router_hosts(config: &Config) -> Vec<String> {
+    let mut hosts: Vec<String> = traefik::service_routers(config)
+        .into_values()
+        .flatten()
+        .flat_map(|router| router.hosts)
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+fn registered_hosts(project: &str, use_dns: bool) -> Vec<String> {
+    if use_dns {
+        return dns::read_snippet(project);
+    }
+    dns::hosts_block(&std::fs::read_to_string(HOSTS_FILE).unwrap_or_default(), project)
+}
+
+async fn save(docker: &Docker, project: &str, use_dns: bool, hosts: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if !use_dns {
+        let content = std::fs::read_to_string(HOSTS_FILE)?;
+        dns::write_hosts_file(Path::new(HOSTS_FILE), &dns::set_hosts_block(&content, project, hosts))?;
+        return Ok(());
+    }
+
+    dns::write_snippet(project, hosts)?;
+    // Docker may not run, the snippet is read once the proxy is started
+    match docker.ping().await {
+        Ok(_) if dns::reload(docker).await? => println!("Reloaded the DNS container"),
+        _ => println!("The DNS container isn't running, the hostnames are used once it is started"),
+    }
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use bollard::container::{ListContainersOptions, LogOutput};
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::Docker;
+use futures_util::StreamExt;
+use tokio::io::AsyncWriteExt;
+use crate::utils::app_config::AppConfig;
+use crate::utils::database::{Compression, Engine};
+use crate::utils::docker_compose::Config;
+use crate::utils::general::format_bytes;
+
+/// Size of the chunks sent to the container, only a few of them are in memory at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Counts the bytes read from the dump, before it's decompressed
+struct CountingReader<R> {
+    inner: R,
+    bytes: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.bytes.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+pub async fn run(
+    docker: &Docker,
+    config: &Config,
+    app_config: &AppConfig,
+    project_root: &Path,
+    file: Option<String>,
+    database: Option<String>,
+    recreate: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service = app_config.database_container.clone().unwrap_or(String::from("db"));
+    let engine = Engine::detect(config, &service)?;
+    let project = config.name.clone().unwrap_or_default();
+
+    let (source, total): (Box<dyn Read + Send>, Option<u64>) = match file.as_deref() {
+        Some("-") => (Box::new(std::io::stdin()), None),
+        file => {
+            let path = match file {
+                Some(file) => PathBuf::from(file),
+                None => {
+                    let dumps_dir = app_config.dumps_dir.clone().unwrap_or(String::from("dumps"));
+                    newest_dump(&project_root.join(dumps_dir))?
+                }
+            };
+            let file = File::open(&path).map_err(|error| format!("Could not open {} ({})", path.display(), error))?;
+            let size = file.metadata()?.len();
+            eprintln!("Importing {} ...", path.display());
+            (Box::new(file), Some(size))
+        }
+    };
+    let imported = import(docker, &project, &service, engine, database.as_deref(), recreate, source, total).await?;
+
+    eprintln!("Imported {} into '{}'", format_bytes(imported), service);
+    Ok(())
+}
+
+/// Streams a dump into the database client of the running service, returns the number of bytes read from `source`
+#[allow(clippy::too_many_arguments)]
+pub async fn import(
+    docker: &Docker,
+    project: &str,
+    service: &str,
+    engine: Engine,
+    database: Option<&str>,
+    recreate: bool,
+    source: Box<dyn Read + Send>,
+    total: Option<u64>,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let container = running_container(docker, project, service).await?;
+
+    let bytes = Arc::new(AtomicU64::new(0));
+    let mut source = BufReader::with_capacity(CHUNK_SIZE, CountingReader { inner: source, bytes: bytes.clone() });
+    let compression = Compression::sniff(&mut source)?;
+    let mut source = compression.decompress(source)?;
+
+    let exec = docker.create_exec(&container, CreateExecOptions {
+        attach_stdin: Some(true),
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        cmd: Some(vec![
+            String::from("sh"),
+            String::from("-c"),
+            engine.import_script(database, recreate),
+        ]),
+        ..Default::default()
+    }).await?;
+    let StartExecResults::Attached { mut output, mut input } = docker.start_exec(&exec.id, None).await? else {
+        return Err("Could not attach to the database client".into());
+    };
+
+    // Reading and decompressing blocks, so it happens on its own thread
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(8);
+    tokio::task::spawn_blocking(move || {
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            let chunk = match source.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(read) => Ok(buffer[..read].to_vec()),
+                Err(error) => Err(error),
+            };
+            let failed = chunk.is_err();
+            if sender.blocking_send(chunk).is_err() || failed {
+                return;
+            }
+        }
+    });
+
+    let show_progress = std::io::stderr().is_terminal();
+    let write = async {
+        let mut last_progress = Instant::now();
+        while let Some(chunk) = receiver.recv().await {
+            input.write_all(&chunk?).await?;
+            if show_progress && last_progress.elapsed() > Duration::from_millis(200) {
+                print_progress(bytes.load(Ordering::Relaxed), total);
+                last_progress = Instant::now();
+            }
+        }
+        // Closing stdin lets the client finish
+        input.shutdown().await?;
+        Ok::<(), std::io::Error>(())
+    };
+    let read = async {
+        while let Some(message) = output.next().await {
+            match message? {
+                LogOutput::StdOut { message } | LogOutput::StdErr { message } => {
+                    std::io::stderr().write_all(&message)?;
+                }
+                _ => {}
+            }
+        }
+        Ok::<(), Box<dyn std::error::Error>>(())
+    };
+    let (written, read) = tokio::join!(write, read);
+    if show_progress {
+        print_progress(bytes.load(Ordering::Relaxed), total);
+        eprintln!();
+    }
+
+    // A client which stopped early is the cause of a failed write, so its exit code is checked first.
+    // The exec may still be finishing when the output is closed, then it has no exit code yet.
+    let started_waiting = Instant::now();
+    let mut inspected = docker.inspect_exec(&exec.id).await?;
+    while inspected.running == Some(true) && started_waiting.elapsed() < Duration::from_secs(10) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        inspected = docker.inspect_exec(&exec.id).await?;
+    }
+    let exit_code = inspected.exit_code;
+    if exit_code != Some(0) {
+        return Err(format!("Importing into '{}' failed (exit code {})", service, exit_code.unwrap_or(-1)).into());
+    }
+    written?;
+    read?;
+
+    Ok(bytes.load(Ordering::Relaxed))
+}
+
+/// The id of the running container of a compose service
+async fn running_container(docker: &Docker, project: &str, service: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        filters: HashMap::from([(
+            String::from("label"),
+            vec![
+                format!("com.docker.compose.project={}", project),
+                format!("com.docker.compose.service={}", service),
+            ],
+        )]),
+        ..Default::default()
+    })).await?;
+
+    containers
+        .into_iter()
+        .find_map(|container| container.id)
+        .ok_or_else(|| format!("The database service '{}' is not running, start the project first", service).into())
+}
+
+/// The most recently modified dump in the dumps directory
+fn newest_dump(dumps_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let entries = std::fs::read_dir(dumps_dir)
+        .map_err(|_| format!("No dump given and {} does not exist", dumps_dir.display()))?;
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            path.is_file() && [".sql", ".sql.gz", ".sql.zst"].iter().any(|extension| name.ends_with(extension))
+        })
+        .max_by_key(|path| path.metadata().and_then(|metadata| metadata.modified()).ok())
+        .ok_or_else(|| format!("No dump found in {}", dumps_dir.display()).into())
+}
+
+fn print_progress(bytes: u64, total: Option<u64>) {
+    match total {
+        Some(total) if total > 0 => eprint!(
+            "\r{} / {} ({}%)   ",
+            format_bytes(bytes),
+            format_bytes(total),
+            bytes * 100 / total
+        ),
+        _ => eprint!("\r{}   ", format_bytes(bytes)),
+    }
+}
@@ -0,0 +1,418 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use bollard::container::{Config as ContainerConfig, RemoveContainerOptions, UploadToContainerOptions, WaitContainerOptions};
+use bollard::image::CreateImageOptions;
+use bollard::models::{HostConfig, Mount, MountTypeEnum};
+use bollard::Docker;
+use futures_util::{StreamExt, TryStreamExt};
+use crate::utils::app_config::{AppConfig, UploadDir};
+use crate::utils::database::shell_quote;
+use crate::utils::docker_compose::Config;
+
+/// Runs in a short-lived container to write into volumes, which the host can't reach
+const HELPER_IMAGE: &str = "busybox:latest";
+
+/// Size of the chunks sent to Docker, only a few of them are in memory at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What `import-files` copies from
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Directory(PathBuf),
+    Tar(PathBuf),
+    TarGz(PathBuf),
+    Zip(PathBuf),
+}
+
+impl Source {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        if path.is_dir() {
+            return Ok(Source::Directory(path.to_path_buf()));
+        }
+        if !path.is_file() {
+            return Err(format!("{} does not exist", path.display()));
+        }
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+        if name.ends_with(".tar") {
+            Ok(Source::Tar(path.to_path_buf()))
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Source::TarGz(path.to_path_buf()))
+        } else if name.ends_with(".zip") {
+            Ok(Source::Zip(path.to_path_buf()))
+        } else {
+            Err(format!("{} is neither a directory nor a .tar, .tar.gz or .zip archive", path.display()))
+        }
+    }
+
+    /// Reads the whole archive, so a broken one is noticed before `--delete` removed anything
+    fn check(&self) -> Result<(), String> {
+        let read = || -> Result<(), Box<dyn std::error::Error>> {
+            match self {
+                Source::Directory(directory) => fs::read_dir(directory).map(|_| ())?,
+                Source::Tar(path) => read_tar(File::open(path)?)?,
+                Source::TarGz(path) => read_tar(flate2::read::GzDecoder::new(BufReader::new(File::open(path)?)))?,
+                Source::Zip(path) => {
+                    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+                    for index in 0..archive.len() {
+                        std::io::copy(&mut archive.by_index(index)?, &mut std::io::sink())?;
+                    }
+                }
+            }
+            Ok(())
+        };
+        read().map_err(|error| format!("Could not read {}: {}", self.path().display(), error))
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Source::Directory(path) | Source::Tar(path) | Source::TarGz(path) | Source::Zip(path) => path,
+        }
+    }
+}
+
+fn read_tar(reader: impl Read) -> std::io::Result<()> {
+    for entry in tar::Archive::new(reader).entries()? {
+        std::io::copy(&mut entry?, &mut std::io::sink())?;
+    }
+    Ok(())
+}
+
+/// A source directory in the target would be deleted with `--delete`, one around it copied into itself
+fn check_overlap(source: &Source, directory: &Path) -> Result<(), String> {
+    let Source::Directory(source) = source else {
+        return Ok(());
+    };
+    let absolute = |path: &Path| path.canonicalize().unwrap_or_else(|_| std::path::absolute(path).unwrap_or(path.to_path_buf()));
+    let (source_path, target_path) = (absolute(source), absolute(directory));
+    if source_path.starts_with(&target_path) || target_path.starts_with(&source_path) {
+        return Err(format!("Can't import {} into {}, one is inside the other", source.display(), directory.display()));
+    }
+    Ok(())
+}
+
+/// Where the files of an upload directory end up
+#[derive(Debug, PartialEq)]
+enum Target {
+    /// A directory on the host, in a bind mount
+    Directory(PathBuf),
+    /// A directory in a named volume
+    Volume { name: String, subpath: String },
+}
+
+/// Sends everything written to it as chunks to a channel, to stream a tar archive to Docker
+struct ChannelWriter(tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(buffer.to_vec()))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "The upload was cancelled"))?;
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+pub async fn run(
+    docker: &Docker,
+    config: &Config,
+    app_config: &AppConfig,
+    source: String,
+    target: Option<String>,
+    delete: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let upload_dirs = app_config.upload_dirs.clone().unwrap_or_default();
+    let upload_dir = match &target {
+        Some(target) => upload_dirs
+            .iter()
+            .find(|upload_dir| upload_dir.path.trim_end_matches('/') == target.trim_end_matches('/'))
+            .ok_or_else(|| format!("'{}' is not one of the `upload_dirs` in the config", target))?,
+        None => upload_dirs
+            .first()
+            .ok_or("There are no `upload_dirs` in the config to import the files into")?,
+    };
+    let source = Source::from_path(Path::new(&source))?;
+    let target = resolve_target(config, upload_dir)?;
+    if let Target::Directory(directory) = &target {
+        check_overlap(&source, directory)?;
+    }
+    source.check()?;
+
+    match target {
+        Target::Directory(directory) => {
+            println!("Importing into {} ...", directory.display());
+            import_into_directory(&source, &directory, delete)?;
+        }
+        Target::Volume { name, subpath } => {
+            println!("Importing into the volume {} ...", name);
+            import_into_volume(docker, &source, &name, &subpath, delete).await?;
+        }
+    }
+
+    println!("Imported the files into {} of '{}'", upload_dir.path, upload_dir.service);
+    Ok(())
+}
+
+/// Finds the bind mount or volume the upload directory is in, the deepest one if they are nested
+fn resolve_target(config: &Config, upload_dir: &UploadDir) -> Result<Target, String> {
+    let service = config.services
+        .get(&upload_dir.service)
+        .ok_or_else(|| format!("The service '{}' of the upload directory does not exist", upload_dir.service))?;
+    let path = upload_dir.path.trim_end_matches('/');
+
+    let volume = service.volumes
+        .iter()
+        .filter(|volume| {
+            let target = volume.target.trim_end_matches('/');
+            path == target || path.starts_with(&format!("{}/", target))
+        })
+        .max_by_key(|volume| volume.target.trim_end_matches('/').len())
+        .ok_or_else(|| format!(
+            "{} of '{}' is not in a bind mount or volume, the files would be lost with the container",
+            upload_dir.path, upload_dir.service
+        ))?;
+    let rest = path[volume.target.trim_end_matches('/').len()..].trim_start_matches('/');
+
+    match (volume.volume_type.as_str(), &volume.source) {
+        ("bind", Some(source)) if rest.is_empty() => Ok(Target::Directory(PathBuf::from(source))),
+        ("bind", Some(source)) => Ok(Target::Directory(Path::new(source).join(rest))),
+        ("volume", Some(source)) => {
+            // Volumes without an explicit name are prefixed with the project name by compose
+            let name = config.volumes
+                .get(source)
+                .and_then(|volume| volume.name.clone())
+                .unwrap_or_else(|| format!("{}_{}", config.name.clone().unwrap_or_default(), source));
+            let subpath = volume.volume
+                .as_ref()
+                .and_then(|volume| volume.subpath.clone())
+                .map_or(rest.to_string(), |subpath| {
+                    if rest.is_empty() { subpath } else { format!("{}/{}", subpath.trim_end_matches('/'), rest) }
+                });
+            Ok(Target::Volume { name, subpath })
+        }
+        (volume_type, _) => Err(format!(
+            "{} of '{}' is in a {} mount, which can't be imported into",
+            upload_dir.path, upload_dir.service, if volume_type == "volume" { "anonymous volume" } else { volume_type }
+        )),
+    }
+}
+
+fn import_into_directory(source: &Source, directory: &Path, delete: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if delete && directory.is_dir() {
+        // The directory itself may be a mount point, only its content is removed
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() && !path.is_symlink() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    fs::create_dir_all(directory)?;
+
+    match source {
+        Source::Directory(source) => copy_directory(source, directory)?,
+        Source::Tar(path) => unpack(File::open(path)?, directory)?,
+        Source::TarGz(path) => unpack(flate2::read::GzDecoder::new(BufReader::new(File::open(path)?)), directory)?,
+        Source::Zip(path) => zip::ZipArchive::new(File::open(path)?)?.extract(directory)?,
+    }
+    Ok(())
+}
+
+fn unpack(reader: impl Read, directory: &Path) -> std::io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_overwrite(true);
+    archive.unpack(directory)
+}
+
+/// Copies a directory recursively, `fs::copy` keeps the permissions of the files
+fn copy_directory(source: &Path, target: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = target.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_directory(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            let link = fs::read_link(entry.path())?;
+            let _ = fs::remove_file(&target);
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(link, &target)?;
+            #[cfg(windows)]
+            fs::copy(entry.path().parent().unwrap_or(source).join(link), &target).map(|_| ())?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    fs::set_permissions(target, fs::metadata(source)?.permissions())
+}
+
+/// Volumes are written through a helper container: it prepares the directory, then the files are
+/// streamed into it as a tar archive
+async fn import_into_volume(
+    docker: &Docker,
+    source: &Source,
+    volume: &str,
+    subpath: &str,
+    delete: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if docker.inspect_image(HELPER_IMAGE).await.is_err() {
+        println!("Pulling {} ...", HELPER_IMAGE);
+        docker
+            .create_image(Some(CreateImageOptions { from_image: HELPER_IMAGE, ..Default::default() }), None, None)
+            .try_collect::<Vec<_>>()
+            .await?;
+    }
+
+    let directory = Path::new("/target").join(subpath).to_string_lossy().to_string();
+    let mut script = format!("mkdir -p {}", shell_quote(&directory));
+    if delete {
+        script += &format!(" && find {} -mindepth 1 -delete", shell_quote(&directory));
+    }
+    let container = docker.create_container::<String, String>(None, ContainerConfig {
+        image: Some(String::from(HELPER_IMAGE)),
+        cmd: Some(vec![String::from("sh"), String::from("-c"), script]),
+        host_config: Some(HostConfig {
+            mounts: Some(vec![Mount {
+                target: Some(String::from("/target")),
+                source: Some(volume.to_string()),
+                typ: Some(MountTypeEnum::VOLUME),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }).await?;
+
+    let result = upload(docker, &container.id, source, &directory).await;
+    docker.remove_container(&container.id, Some(RemoveContainerOptions { force: true, ..Default::default() })).await?;
+    result
+}
+
+async fn upload(docker: &Docker, container: &str, source: &Source, directory: &str) -> Result<(), Box<dyn std::error::Error>> {
+    docker.start_container::<String>(container, None).await?;
+    // Fails for a non-zero exit code
+    docker.wait_container(container, None::<WaitContainerOptions<String>>).try_collect::<Vec<_>>().await?;
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+    let source = source.clone();
+    let writer = tokio::task::spawn_blocking(move || write_tar(&source, BufWriter::with_capacity(CHUNK_SIZE, ChannelWriter(sender))));
+    let body = hyper::Body::wrap_stream(futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    }).boxed());
+
+    let uploaded = docker
+        .upload_to_container(container, Some(UploadToContainerOptions { path: directory, ..Default::default() }), body)
+        .await;
+    let written = writer.await?;
+    // A failed upload cancels the writer, so the error of Docker is the one that explains it
+    uploaded?;
+    written.map_err(|error| error.to_string())?;
+    Ok(())
+}
+
+/// Writes the source as a tar archive, keeping the permissions of the files
+fn write_tar(source: &Source, mut writer: impl Write) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match source {
+        Source::Directory(directory) => {
+            let mut builder = tar::Builder::new(&mut writer);
+            builder.follow_symlinks(false);
+            builder.append_dir_all(".", directory)?;
+            builder.finish()?;
+        }
+        Source::Tar(path) => {
+            std::io::copy(&mut File::open(path)?, &mut writer)?;
+        }
+        Source::TarGz(path) => {
+            std::io::copy(&mut flate2::read::GzDecoder::new(BufReader::new(File::open(path)?)), &mut writer)?;
+        }
+        Source::Zip(path) => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            let mut builder = tar::Builder::new(&mut writer);
+            let modified = chrono::Utc::now().timestamp() as u64;
+            for index in 0..archive.len() {
+                let mut file = archive.by_index(index)?;
+                let Some(name) = file.enclosed_name().map(Path::to_path_buf) else {
+                    continue;
+                };
+                let mut header = tar::Header::new_gnu();
+                header.set_mtime(modified);
+                if file.is_dir() {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_mode(file.unix_mode().unwrap_or(0o755) & 0o7777);
+                    header.set_size(0);
+                    builder.append_data(&mut header, name, std::io::empty())?;
+                } else {
+                    header.set_mode(file.unix_mode().unwrap_or(0o644) & 0o7777);
+                    header.set_size(file.size());
+                    builder.append_data(&mut header, name, &mut file)?;
+                }
+            }
+            builder.finish()?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn resolve_upload_targets() {
+    let config: Config = serde_yaml::from_str(
+        "name: shop\n\
+         services:\n  \
+           php:\n    \
+             volumes:\n      \
+               - {type: bind, source: /home/dev/shop, target: /var/www/html}\n      \
+               - {type: volume, source: media, target: /var/www/html/public/media}\n      \
+               - {type: volume, source: cache, target: /var/cache, volume: {subpath: php}}\n      \
+               - {type: tmpfs, target: /tmp}\n\
+         volumes:\n  \
+           media: {}\n  \
+           cache: {name: shared-cache}\n",
+    ).unwrap();
+    let upload_dir = |path: &str| UploadDir { service: String::from("php"), path: path.to_string() };
+
+    assert_eq!(
+        resolve_target(&config, &upload_dir("/var/www/html/public/uploads/")),
+        Ok(Target::Directory(PathBuf::from("/home/dev/shop/public/uploads")))
+    );
+    assert_eq!(
+        resolve_target(&config, &upload_dir("/var/www/html/public/media/images")),
+        Ok(Target::Volume { name: String::from("shop_media"), subpath: String::from("images") })
+    );
+    assert_eq!(
+        resolve_target(&config, &upload_dir("/var/cache")),
+        Ok(Target::Volume { name: String::from("shared-cache"), subpath: String::from("php") })
+    );
+    assert!(resolve_target(&config, &upload_dir("/tmp/uploads")).is_err());
+    assert!(resolve_target(&config, &upload_dir("/srv/uploads")).is_err());
+}
+
+#[test]
+fn check_sources_before_deleting() -> Result<(), Box<dyn std::error::Error>> {
+    use assert_fs::prelude::*;
+
+    let temp = assert_fs::TempDir::new()?;
+    temp.child("uploads/image.png").write_str("png")?;
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    write_tar(&Source::Directory(temp.child("uploads").to_path_buf()), &mut gzip).map_err(|error| error.to_string())?;
+    let archive = gzip.finish()?;
+    temp.child("uploads.tar.gz").write_binary(&archive)?;
+    temp.child("truncated.tar.gz").write_binary(&archive[..archive.len() / 2])?;
+
+    assert_eq!(Source::from_path(&temp.child("uploads.tar.gz"))?.check(), Ok(()));
+    assert!(Source::from_path(&temp.child("truncated.tar.gz"))?.check().is_err());
+
+    let uploads = Source::from_path(&temp.child("uploads"))?;
+    assert!(check_overlap(&uploads, temp.path()).is_err());
+    assert!(check_overlap(&uploads, &temp.child("uploads/new")).is_err());
+    assert_eq!(check_overlap(&uploads, &temp.child("public")), Ok(()));
+    Ok(())
+}
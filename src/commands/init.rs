@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::path::PathBuf;
+use crate::utils::compose_loader::project_name_from_dir;
+use crate::utils::general::{Asset, Template};
+
+pub fn run(template: String, name: Option<String>, tld: String, force: bool, list: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if list {
+        println!("Available templates:");
+        for template in templates() {
+            println!("  {}", template);
+        }
+        return Ok(());
+    }
+
+    let prefix = format!("{}/", template);
+    let files: Vec<String> = Template::iter()
+        .filter(|file| file.starts_with(&prefix))
+        .map(|file| file.to_string())
+        .collect();
+    if files.is_empty() {
+        let templates: Vec<String> = templates().into_iter().collect();
+        return Err(format!("Unknown template '{}', available are: {}", template, templates.join(", ")).into());
+    }
+
+    let project_dir = env::current_dir()?;
+    let name = name.unwrap_or_else(|| project_name_from_dir(&project_dir));
+
+    let mut rendered: Vec<(PathBuf, Vec<u8>)> = files
+        .iter()
+        .map(|file| {
+            let content = Template::get(file).unwrap().data.into_owned();
+            (project_dir.join(&file[prefix.len()..]), render(content, &name, &tld))
+        })
+        .collect();
+    // The `.env` sets the variables of the Traefik labels and `.dev-cli.dist.yml` marks the project root
+    for file in Asset::iter().filter(|file| file.starts_with("init/")) {
+        let path = project_dir.join(&file["init/".len()..]);
+        if !rendered.iter().any(|(rendered_path, _)| *rendered_path == path) {
+            rendered.push((path, render(Asset::get(&file).unwrap().data.into_owned(), &name, &tld)));
+        }
+    }
+
+    let existing: Vec<String> = rendered
+        .iter()
+        .filter(|(path, _)| path.exists())
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+    if !existing.is_empty() && !force {
+        return Err(format!(
+            "Not overwriting existing files (use --force to overwrite):\n  {}",
+            existing.join("\n  ")
+        ).into());
+    }
+
+    for (path, content) in rendered {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        println!("Created {}", path.display());
+    }
+    println!("Project '{}' initialized, run `dev-cli start` to start it", name);
+
+    Ok(())
+}
+
+fn templates() -> BTreeSet<String> {
+    Template::iter()
+        .filter_map(|file| file.split_once('/').map(|(template, _)| template.to_string()))
+        .collect()
+}
+
+/// Replaces `{{ name }}` and `{{ tld }}` in text files, other files are copied as they are
+fn render(content: Vec<u8>, name: &str, tld: &str) -> Vec<u8> {
+    match String::from_utf8(content) {
+        Ok(text) => text
+            .replace("{{ name }}", name)
+            .replace("{{ tld }}", tld)
+            .into_bytes(),
+        Err(error) => error.into_bytes(),
+    }
+}
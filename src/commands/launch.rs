@@ -0,0 +1,42 @@
+use crate::utils::docker_compose::Config;
+use crate::utils::general::open_with_default_app;
+use crate::utils::traefik;
+
+pub fn run(config: &Config, service: Option<String>, print: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut service_routers = traefik::service_routers(config);
+
+    let routers = match service {
+        Some(service) => match service_routers.remove(&service) {
+            Some(routers) => routers,
+            None => return Err(format!("The service '{}' has no Traefik router with a Host() rule", service).into()),
+        },
+        None => match service_routers.len() {
+            0 => return Err("No service has a Traefik router with a Host() rule".into()),
+            1 => service_routers.into_values().next().unwrap(),
+            _ => {
+                let services: Vec<String> = service_routers.into_keys().collect();
+                return Err(format!(
+                    "Multiple services have a Traefik router, please choose one: {}",
+                    services.join(", ")
+                ).into());
+            }
+        },
+    };
+
+    let url = match traefik::primary_url(&routers) {
+        Some(url) => url,
+        None => return Err("No Traefik router with a hostname found".into()),
+    };
+    // An unset variable like `${TLD}` leaves the hostname incomplete
+    if url.ends_with('.') {
+        return Err(format!("The hostname of {} is incomplete, are all variables set in .env?", url).into());
+    }
+
+    if print {
+        println!("{}", url);
+    } else {
+        println!("Opening {} ...", url);
+        open_with_default_app(&url)?;
+    }
+    Ok(())
+}
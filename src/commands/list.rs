@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::commands::status::service_statuses;
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::{format_duration, get_app_config, print_table, OutputFormat};
+use crate::utils::project_registry::{scan_workspaces, ProjectRegistry};
+use crate::utils::traefik;
+
+/// How deep the workspace directories are searched for projects
+const WORKSPACE_DEPTH: usize = 2;
+/// Projects which were not started for this long are stale
+const STALE_AFTER_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize)]
+struct ProjectEntry {
+    name: String,
+    root: String,
+    url: Option<String>,
+    /// `running`, `stopped` or `missing` if the root directory no longer exists
+    state: String,
+    /// `None` for projects which were found in a workspace but never started
+    last_started: Option<DateTime<Utc>>,
+}
+
+impl ProjectEntry {
+    fn is_stale(&self) -> bool {
+        let recently_started = self.last_started.is_some_and(|last_started| {
+            Utc::now() - last_started <= chrono::Duration::days(STALE_AFTER_DAYS)
+        });
+        self.state == "missing" || self.state != "running" && !recently_started
+    }
+}
+
+pub async fn run(docker: &Docker, running: bool, stale: bool, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = ProjectRegistry::load()?;
+    let workspaces: Vec<PathBuf> = AppConfig::global()?
+        .workspaces
+        .unwrap_or_default()
+        .iter()
+        .map(|workspace| expand_home(workspace))
+        .collect();
+
+    let mut projects: Vec<(PathBuf, Option<String>, Option<DateTime<Utc>>)> = registry.projects
+        .into_iter()
+        .map(|project| (project.root, Some(project.name), Some(project.last_used)))
+        .collect();
+    for root in scan_workspaces(&workspaces, WORKSPACE_DEPTH) {
+        if !projects.iter().any(|(known, _, _)| *known == root) {
+            projects.push((root, None, None));
+        }
+    }
+
+    let mut entries = vec![];
+    for (root, name, last_started) in projects {
+        let config = if root.is_dir() { compose_config(&root) } else { None };
+        let name = name
+            .or_else(|| config.as_ref().and_then(|config| config.name.clone()))
+            .unwrap_or_else(|| root.file_name().unwrap_or_default().to_string_lossy().to_string());
+        let url = config.as_ref().and_then(|config| {
+            let routers: Vec<_> = traefik::service_routers(config).into_values().flatten().collect();
+            traefik::primary_url(&routers)
+        });
+
+        let state = if !root.is_dir() {
+            "missing"
+        } else if service_statuses(docker, &name, None).await?.iter().any(|status| status.state == "running") {
+            "running"
+        } else {
+            "stopped"
+        };
+
+        entries.push(ProjectEntry {
+            name,
+            root: root.display().to_string(),
+            url,
+            state: state.to_string(),
+            last_started,
+        });
+    }
+    entries.retain(|entry| (!running || entry.state == "running") && (!stale || entry.is_stale()));
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&entries)?),
+        OutputFormat::Text => {
+            if entries.is_empty() {
+                println!("No projects found");
+                return Ok(());
+            }
+
+            let rows: Vec<Vec<String>> = entries
+                .iter()
+                .map(|entry| vec![
+                    entry.name.clone(),
+                    entry.state.clone(),
+                    entry.url.clone().unwrap_or_default(),
+                    entry.last_started.map_or(String::from("never"), |last_started| {
+                        format!("{} ago", format_duration(Utc::now().signed_duration_since(last_started)))
+                    }),
+                    entry.root.clone(),
+                ])
+                .collect();
+            print_table(&["NAME", "STATE", "URL", "LAST STARTED", "ROOT"], &rows);
+        }
+    }
+    Ok(())
+}
+
+/// The compose config of a project, `None` if it can't be read
+fn compose_config(root: &Path) -> Option<Config> {
+    let app_config = get_app_config(root).ok()?;
+    DockerCompose::new(root.join("compose.yml"))
+        .with_config_from_docker(app_config.compose_config_from_docker.unwrap_or(false))
+        .config()
+        .ok()
+}
+
+/// Workspaces are written like `~/projects` in the config
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use bollard::container::{ListContainersOptions, LogOutput, LogsOptions};
+use bollard::models::ContainerSummary;
+use bollard::Docker;
+use chrono::{DateTime, Local, Utc};
+use futures_util::StreamExt;
+use crate::utils::docker_compose::Config;
+
+/// ANSI colours for the service prefixes, assigned in order
+const COLORS: [u8; 6] = [36, 33, 32, 35, 34, 31];
+
+struct LogLine {
+    container: usize,
+    timestamp: Option<DateTime<Utc>>,
+    text: String,
+}
+
+impl LogLine {
+    /// Splits off the timestamp Docker puts in front of each line with `timestamps`
+    fn parse(container: usize, line: &str) -> Self {
+        let (timestamp, text) = line.split_once(' ').unwrap_or((line, ""));
+        LogLine {
+            container,
+            timestamp: DateTime::parse_from_rfc3339(timestamp)
+                .ok()
+                .map(|timestamp| timestamp.with_timezone(&Utc)),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Collects the chunks of a log stream into lines, Docker may split a line across chunks
+#[derive(Default)]
+struct LineBuffer(Vec<u8>);
+
+impl LineBuffer {
+    /// Returns the lines completed by the chunk, the rest is kept until its newline arrives
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.0.extend_from_slice(chunk);
+        let Some(end) = self.0.iter().rposition(|byte| *byte == b'\n') else {
+            return vec![];
+        };
+        let complete: Vec<u8> = self.0.drain(..=end).collect();
+        String::from_utf8_lossy(&complete).lines().map(String::from).collect()
+    }
+
+    /// The last line of a stream which ended without a newline
+    fn finish(self) -> Option<String> {
+        (!self.0.is_empty()).then(|| String::from_utf8_lossy(&self.0).to_string())
+    }
+}
+
+pub async fn run(
+    docker: &Docker,
+    config: &Config,
+    services: Vec<String>,
+    follow: bool,
+    since: Option<String>,
+    tail: String,
+    grep: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    config.select_services(&services)?;
+    let project = config.name.clone().unwrap_or_default();
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        all: true,
+        filters: HashMap::from([(
+            String::from("label"),
+            vec![format!("com.docker.compose.project={}", project)],
+        )]),
+        ..Default::default()
+    })).await?;
+
+    let containers = select_containers(containers, &services);
+    if containers.is_empty() {
+        return Err("No containers found, is the project started?".into());
+    }
+
+    let since = match since {
+        Some(since) => parse_since(&since)?,
+        None => 0,
+    };
+    let prefix_width = containers.iter().map(|(_, prefix)| prefix.len()).max().unwrap_or(0);
+    let colored = std::io::stdout().is_terminal();
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<LogLine>();
+    for (index, (id, name)) in containers.iter().enumerate() {
+        let docker = docker.clone();
+        let id = id.clone();
+        let name = name.clone();
+        let sender = sender.clone();
+        let options = LogsOptions::<String> {
+            follow,
+            stdout: true,
+            stderr: true,
+            since,
+            timestamps: true,
+            tail: tail.clone(),
+            ..Default::default()
+        };
+        tokio::spawn(async move {
+            let mut stream = docker.logs(&id, Some(options));
+            // Separate buffers, so a partial line of stdout isn't joined with one of stderr
+            let (mut stdout, mut stderr) = (LineBuffer::default(), LineBuffer::default());
+            while let Some(output) = stream.next().await {
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        eprintln!("Could not read the logs of {} ({})", name, error);
+                        break;
+                    }
+                };
+                let lines = match output {
+                    LogOutput::StdErr { message } => stderr.push(&message),
+                    output => stdout.push(&output.into_bytes()),
+                };
+                for line in lines {
+                    if sender.send(LogLine::parse(index, &line)).is_err() {
+                        return;
+                    }
+                }
+            }
+            for line in [stdout.finish(), stderr.finish()].into_iter().flatten() {
+                let _ = sender.send(LogLine::parse(index, &line));
+            }
+        });
+    }
+    drop(sender);
+
+    let print_line = |line: &LogLine| {
+        if let Some(grep) = &grep {
+            if !line.text.contains(grep.as_str()) {
+                return;
+            }
+        }
+        let time = line.timestamp
+            .map(|timestamp| timestamp.with_timezone(&Local).format("%H:%M:%S%.3f").to_string())
+            .unwrap_or_else(|| " ".repeat(12));
+        let prefix = format!("{:width$}", containers[line.container].1, width = prefix_width);
+        if colored {
+            let color = COLORS[line.container % COLORS.len()];
+            println!("\x1b[{}m{} {} |\x1b[0m {}", color, prefix, time, line.text);
+        } else {
+            println!("{} {} | {}", prefix, time, line.text);
+        }
+    };
+
+    let print = async {
+        if follow {
+            while let Some(line) = receiver.recv().await {
+                print_line(&line);
+            }
+        } else {
+            // Without following all logs are available, so the services are interleaved by time
+            let mut lines = vec![];
+            while let Some(line) = receiver.recv().await {
+                lines.push(line);
+            }
+            lines.sort_by_key(|line| line.timestamp);
+            for line in &lines {
+                print_line(line);
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = print => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    Ok(())
+}
+
+/// The ids of the containers of the given services, all if none are given, with their prefix
+/// like `php-1`, sorted by the prefix
+fn select_containers(containers: Vec<ContainerSummary>, services: &[String]) -> Vec<(String, String)> {
+    let mut containers: Vec<(String, String)> = containers
+        .into_iter()
+        .filter_map(|container| {
+            let labels = container.labels.unwrap_or_default();
+            let service = labels.get("com.docker.compose.service")?.clone();
+            let number = labels.get("com.docker.compose.container-number").cloned().unwrap_or_default();
+            Some((container.id?, service, number))
+        })
+        .filter(|(_, service, _)| services.is_empty() || services.contains(service))
+        .map(|(id, service, number)| (id, format!("{}-{}", service, number)))
+        .collect();
+    containers.sort_by(|a, b| a.1.cmp(&b.1));
+    containers
+}
+
+/// Parses `30s`, `10m`, `2h`, `1d`, a unix timestamp or an RFC 3339 date into a unix timestamp
+fn parse_since(since: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    if let Ok(timestamp) = since.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(since) {
+        return Ok(date.timestamp());
+    }
+
+    let unit_start = since.len().saturating_sub(1);
+    if !since.is_char_boundary(unit_start) {
+        return Err(format!("Invalid value for --since: {}", since).into());
+    }
+    let (amount, unit) = since.split_at(unit_start);
+    let seconds = match (amount.parse::<i64>(), unit) {
+        (Ok(amount), "s") => amount,
+        (Ok(amount), "m") => amount * 60,
+        (Ok(amount), "h") => amount * 3600,
+        (Ok(amount), "d") => amount * 86400,
+        _ => return Err(format!("Invalid value for --since: {}", since).into()),
+    };
+    Ok(Utc::now().timestamp() - seconds)
+}
+
+#[test]
+fn join_lines_split_across_chunks() {
+    let mut buffer = LineBuffer::default();
+
+    assert!(buffer.push(b"2024-05-01T10:00:00.000000000Z GET /in").is_empty());
+    assert_eq!(buffer.push(b"dex.php 200\n2024-05-01T10:00:01.000000000Z GET /"), ["2024-05-01T10:00:00.000000000Z GET /index.php 200"]);
+    assert_eq!(buffer.push(b"a\r\nb\n"), ["2024-05-01T10:00:01.000000000Z GET /a", "b"]);
+    assert!(buffer.push(b"partial").is_empty());
+    assert_eq!(buffer.finish().as_deref(), Some("partial"));
+
+    let line = LogLine::parse(1, "2024-05-01T10:00:00.000000000Z GET /index.php 200");
+    assert_eq!(line.container, 1);
+    assert_eq!(line.timestamp.map(|timestamp| timestamp.timestamp()), Some(1714557600));
+    assert_eq!(line.text, "GET /index.php 200");
+}
+
+#[test]
+fn select_containers_of_services() {
+    let container = |id: &str, service: Option<&str>, number: &str| ContainerSummary {
+        id: Some(id.to_string()),
+        labels: Some(service.map_or_else(HashMap::new, |service| HashMap::from([
+            (String::from("com.docker.compose.service"), service.to_string()),
+            (String::from("com.docker.compose.container-number"), number.to_string()),
+        ]))),
+        ..Default::default()
+    };
+    let containers = || vec![
+        container("c", Some("php"), "2"),
+        container("a", Some("nginx"), "1"),
+        container("b", Some("php"), "1"),
+        container("d", None, ""),
+    ];
+
+    let prefixes = |selected: Vec<(String, String)>| -> Vec<String> {
+        selected.into_iter().map(|(id, prefix)| format!("{}:{}", id, prefix)).collect()
+    };
+    assert_eq!(prefixes(select_containers(containers(), &[])), ["a:nginx-1", "b:php-1", "c:php-2"]);
+    assert_eq!(prefixes(select_containers(containers(), &[String::from("php")])), ["b:php-1", "c:php-2"]);
+    assert!(select_containers(containers(), &[String::from("db")]).is_empty());
+}
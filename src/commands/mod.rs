@@ -1 +1,22 @@
 pub mod exec;
+pub mod run;
+pub mod status;
+pub mod global_status;
+pub mod poweroff;
+pub mod shell;
+pub mod launch;
+pub mod init;
+pub mod logs;
+pub mod export_db;
+pub mod import_db;
+pub mod snapshot;
+pub mod completion;
+pub mod config;
+pub mod describe;
+pub mod delete;
+pub mod clean;
+pub mod hostname;
+pub mod cert;
+pub mod list;
+pub mod import_files;
+pub mod service;
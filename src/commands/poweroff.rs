@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use bollard::container::{ListContainersOptions, StopContainerOptions};
+use bollard::models::ContainerSummary;
+use bollard::Docker;
+use tokio::task::JoinSet;
+use crate::utils::general::DEV_CLI_NETWORK;
+use crate::utils::proxy;
+
+pub async fn run(docker: &Docker, parallel: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let proxy_project = proxy::project_name();
+
+    // Every project using the proxy is attached to the shared network
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        filters: HashMap::from([(String::from("network"), vec![String::from(DEV_CLI_NETWORK)])]),
+        ..Default::default()
+    })).await?;
+    let projects = projects(containers, &proxy_project);
+
+    let mut failed = vec![];
+    if parallel {
+        let mut tasks = JoinSet::new();
+        for project in projects {
+            let docker = docker.clone();
+            tasks.spawn(async move {
+                let result = stop_project(&docker, &project).await;
+                (project, result)
+            });
+        }
+        while let Some(task) = tasks.join_next().await {
+            let (project, result) = task?;
+            report(&project, result, &mut failed);
+        }
+    } else {
+        for project in projects {
+            let result = stop_project(docker, &project).await;
+            report(&project, result, &mut failed);
+        }
+    }
+
+    let result = stop_project(docker, &proxy_project).await;
+    report("dev-cli proxy (Traefik, DNS)", result, &mut failed);
+
+    if !failed.is_empty() {
+        return Err(format!("Could not stop: {}", failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+/// The compose projects of the containers, without the proxy stack which is stopped last
+fn projects(containers: Vec<ContainerSummary>, proxy_project: &str) -> Vec<String> {
+    let mut projects: Vec<String> = containers
+        .into_iter()
+        .filter_map(|container| container.labels?.remove("com.docker.compose.project"))
+        .filter(|project| project != proxy_project)
+        .collect();
+    projects.sort();
+    projects.dedup();
+    projects
+}
+
+/// Stops all running containers of a compose project and returns how many were stopped
+pub async fn stop_project(docker: &Docker, project: &str) -> Result<usize, bollard::errors::Error> {
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        filters: HashMap::from([(
+            String::from("label"),
+            vec![format!("com.docker.compose.project={}", project)],
+        )]),
+        ..Default::default()
+    })).await?;
+
+    let mut stopped = 0;
+    for container in containers {
+        if let Some(id) = container.id {
+            docker.stop_container(&id, None::<StopContainerOptions>).await?;
+            stopped += 1;
+        }
+    }
+    Ok(stopped)
+}
+
+fn report(project: &str, result: Result<usize, bollard::errors::Error>, failed: &mut Vec<String>) {
+    match result {
+        Ok(0) => println!("{}: nothing running", project),
+        Ok(stopped) => println!("{}: stopped {} container(s)", project, stopped),
+        Err(error) => {
+            println!("{}: could not be stopped ({})", project, error);
+            failed.push(project.to_string());
+        }
+    }
+}
+
+#[test]
+fn select_projects_to_stop() {
+    let container = |project: Option<&str>| ContainerSummary {
+        labels: Some(project.map_or_else(HashMap::new, |project| {
+            HashMap::from([(String::from("com.docker.compose.project"), project.to_string())])
+        })),
+        ..Default::default()
+    };
+    let containers = vec![
+        container(Some("shop")),
+        container(Some("dev-cli")),
+        container(None),
+        container(Some("blog")),
+        container(Some("shop")),
+    ];
+
+    assert_eq!(projects(containers, "dev-cli"), ["blog", "shop"]);
+}
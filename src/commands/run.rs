@@ -0,0 +1,113 @@
+use std::io::{BufRead, BufReader};
+use crate::utils::app_config::{AppConfig, RunCommand, RunCommandStep};
+use crate::utils::docker_compose::DockerCompose;
+
+pub fn run(docker_compose: DockerCompose, app_config: &AppConfig, command: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let run_commands = app_config.run_commands.clone().unwrap_or_default();
+
+    let (name, args) = match command.split_first() {
+        Some((name, args)) => (name, args),
+        None => {
+            if run_commands.is_empty() {
+                println!("No commands defined in the config file");
+            } else {
+                println!("Available commands:");
+                for name in run_commands.keys() {
+                    println!("  {}", name);
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let recipe = match run_commands.get(name) {
+        Some(recipe) => recipe,
+        None => return Err(format!("No command named '{}' in the config file", name).into()),
+    };
+
+    if recipe.parallel {
+        run_parallel(&docker_compose, recipe, args)
+    } else {
+        run_sequential(&docker_compose, recipe, args)
+    }
+}
+
+fn run_sequential(docker_compose: &DockerCompose, recipe: &RunCommand, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    for step in &recipe.commands {
+        docker_compose.exec(step.container.clone(), step.user.clone(), shell_command(step, args))?;
+    }
+    Ok(())
+}
+
+fn run_parallel(docker_compose: &DockerCompose, recipe: &RunCommand, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let services: Vec<String> = recipe.commands
+        .iter()
+        .map(|step| docker_compose.service_or_default(step.container.clone()))
+        .collect::<anyhow::Result<_>>()?;
+    let prefix_width = services.iter().map(|service| service.len()).max().unwrap_or(0);
+
+    let results: Vec<(String, subprocess::Result<subprocess::ExitStatus>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = recipe.commands
+            .iter()
+            .zip(&services)
+            .map(|(step, service)| {
+                let handle = scope.spawn(move || -> subprocess::Result<subprocess::ExitStatus> {
+                    let mut process = docker_compose
+                        .exec_cmd(service.clone(), step.user.clone(), None, shell_command(step, args), false)
+                        .stdin(subprocess::NullFile)
+                        .stdout(subprocess::Redirection::Pipe)
+                        .stderr(subprocess::Redirection::Merge)
+                        .popen()?;
+
+                    let stdout = process.stdout.take().unwrap();
+                    let read = BufReader::new(stdout).lines().try_for_each(|line| {
+                        println!("{:width$} | {}", service, line?, width = prefix_width);
+                        Ok::<(), std::io::Error>(())
+                    });
+
+                    // The process is reaped before a read error is reported
+                    if read.is_err() {
+                        let _ = process.kill();
+                    }
+                    let status = process.wait()?;
+                    read?;
+                    Ok(status)
+                });
+                (format!("{}: {}", service, step.command), handle)
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|(step, handle)| {
+                let result = handle.join().unwrap_or(Err(
+                    subprocess::PopenError::LogicError("the thread printing the output panicked")
+                ));
+                (step, result)
+            })
+            .collect()
+    });
+
+    let failed: Vec<String> = results
+        .into_iter()
+        .filter_map(|(step, result)| match result {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(format!("{} ({:?})", step, status)),
+            Err(error) => Some(format!("{} ({})", step, error)),
+        })
+        .collect();
+
+    if !failed.is_empty() {
+        return Err(format!("{} command(s) failed:\n  {}", failed.len(), failed.join("\n  ")).into());
+    }
+    Ok(())
+}
+
+/// Builds the command for a step, with any extra arguments quoted and appended to it
+fn shell_command(step: &RunCommandStep, args: &[String]) -> Vec<String> {
+    let mut command = step.command.clone();
+    for arg in args {
+        command.push_str(&format!(" '{}'", arg.replace('\'', "'\\''")));
+    }
+    vec![String::from("sh"), String::from("-c"), command]
+}
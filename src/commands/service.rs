@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use bollard::Docker;
+use crate::commands::status::service_statuses;
+use crate::utils::addons::{self, Addon};
+use crate::utils::app_config::{config_files, AppConfig, ConfigLayer};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::{print_table, AddonCommand};
+use crate::utils::yaml_edit;
+
+pub async fn run(
+    docker: &Docker,
+    docker_compose: DockerCompose,
+    config: &Config,
+    app_config: &AppConfig,
+    project_root: &Path,
+    command: AddonCommand,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let enabled = app_config.addons.clone().unwrap_or_default();
+
+    match command {
+        AddonCommand::List => {
+            let rows: Vec<Vec<String>> = addons::catalogue()
+                .into_iter()
+                .map(|addon| vec![
+                    addon.name.clone(),
+                    String::from(if enabled.contains(&addon.name) { "yes" } else { "no" }),
+                    addon.description,
+                ])
+                .collect();
+            print_table(&["NAME", "ENABLED", "DESCRIPTION"], &rows);
+        }
+        AddonCommand::Enable { addon, project } => {
+            let addon = find(&addon)?;
+            if configured(project_root, app_config, project)?.contains(&addon.name) {
+                println!("The add-on '{}' is enabled already", addon.name);
+                return Ok(());
+            }
+            // It isn't enabled, so a service with the same name comes from the files of the project
+            if let Some(service) = addon.services().into_iter().find(|service| config.services.contains_key(service)) {
+                return Err(format!(
+                    "The project has a service '{}' already, which the add-on '{}' would be merged into",
+                    service, addon.name
+                ).into());
+            }
+
+            let addons = save(project_root, app_config, project, |addons| addons.push(addon.name.clone()))?;
+            let is_enabled = addons.contains(&addon.name);
+            addons::write_override(project_root, &AppConfig { addons: Some(addons), ..app_config.clone() })?;
+            if !is_enabled {
+                println!(
+                    "Added '{}' to the project config, but the `addons` of the local config replace that list, add it there too to enable it",
+                    addon.name
+                );
+                return Ok(());
+            }
+            println!("Enabled the add-on '{}'", addon.name);
+
+            if is_running(docker, config).await {
+                docker_compose.up(Some(addon.services().iter().map(String::as_str).collect()), true)?;
+            } else {
+                println!("It is started with the project, run `dev-cli start`");
+            }
+        }
+        AddonCommand::Disable { addon, project } => {
+            let addon = find(&addon)?;
+            if !configured(project_root, app_config, project)?.contains(&addon.name) {
+                println!("The add-on '{}' is not enabled", addon.name);
+                return Ok(());
+            }
+
+            let addons = save(project_root, app_config, project, |addons| addons.retain(|name| *name != addon.name))?;
+            if addons.contains(&addon.name) {
+                println!(
+                    "Removed '{}' from the project config, but it is still enabled in the local config, run `dev-cli service disable {}`",
+                    addon.name, addon.name
+                );
+                return Ok(());
+            }
+            // Compose only knows the containers of the add-on as long as it is in the override file
+            if is_running(docker, config).await {
+                docker_compose.down(Some(addon.services().iter().map(String::as_str).collect()), false)?;
+            }
+            addons::write_override(project_root, &AppConfig { addons: Some(addons), ..app_config.clone() })?;
+            println!("Disabled the add-on '{}', its volumes are kept until `dev-cli delete`", addon.name);
+        }
+    }
+    Ok(())
+}
+
+fn find(name: &str) -> Result<Addon, String> {
+    addons::get(name).ok_or_else(|| {
+        let names: Vec<String> = addons::catalogue().into_iter().map(|addon| addon.name).collect();
+        format!("Unknown add-on '{}', available are: {}", name, names.join(", "))
+    })
+}
+
+async fn is_running(docker: &Docker, config: &Config) -> bool {
+    let project = config.name.clone().unwrap_or_default();
+    service_statuses(docker, &project, None)
+        .await
+        .is_ok_and(|statuses| statuses.iter().any(|status| status.state == "running"))
+}
+
+fn config_file(project_root: &Path, layer: ConfigLayer) -> PathBuf {
+    config_files(Some(project_root))
+        .into_iter()
+        .find(|(file_layer, _)| *file_layer == layer)
+        .unwrap()
+        .1
+}
+
+/// The add-ons `enable` and `disable` change: those in effect, or with `project` those of the shared config
+fn configured(project_root: &Path, app_config: &AppConfig, project: bool) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !project {
+        return Ok(app_config.addons.clone().unwrap_or_default());
+    }
+    let dist = AppConfig::from_file(&config_file(project_root, ConfigLayer::Dist))?;
+    Ok(dist.and_then(|config| config.addons).unwrap_or_default())
+}
+
+/// Changes the `addons` in the local config, or the shared one with `project`, and returns the
+/// list that is in effect afterwards
+fn save(
+    project_root: &Path,
+    app_config: &AppConfig,
+    project: bool,
+    change: impl Fn(&mut Vec<String>),
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let layer = if project { ConfigLayer::Dist } else { ConfigLayer::Local };
+    let local_addons = AppConfig::from_file(&config_file(project_root, ConfigLayer::Local))?.and_then(|config| config.addons);
+
+    // The local list replaces the shared one, so it starts from what is in effect
+    let mut addons = configured(project_root, app_config, project)?;
+    change(&mut addons);
+
+    let file = config_file(project_root, layer);
+    let content = if file.is_file() { std::fs::read_to_string(&file)? } else { String::new() };
+    std::fs::write(&file, yaml_edit::set_value(&content, &["addons"], &serde_json::to_string(&addons)?)?)?;
+
+    match local_addons {
+        Some(local_addons) if project => {
+            eprintln!("The local config sets `addons` too, which replaces the list of the project");
+            Ok(local_addons)
+        }
+        None if !project && !configured(project_root, app_config, true)?.is_empty() => {
+            eprintln!(
+                "The local config now has its own `addons`, later changes to those of the project are hidden \
+                 until you remove it from {}",
+                config_file(project_root, ConfigLayer::Local).display()
+            );
+            Ok(addons)
+        }
+        _ => Ok(addons),
+    }
+}
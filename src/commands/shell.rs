@@ -0,0 +1,53 @@
+use crate::utils::app_config::AppConfig;
+use crate::utils::docker_compose::{Config, DockerCompose};
+
+/// Tried in this order if no shell is configured for the service
+const SHELLS: [&str; 4] = ["bash", "zsh", "ash", "sh"];
+
+pub fn run(docker_compose: DockerCompose, config: &Config, app_config: &AppConfig, service: Option<String>, user: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let service = docker_compose.service_or_default(service)?;
+    let service_config = app_config.services
+        .as_ref()
+        .and_then(|services| services.get(&service))
+        .cloned()
+        .unwrap_or_default();
+
+    let user = user.or(service_config.user);
+    let shell = match service_config.shell {
+        Some(shell) => shell,
+        None => detect_shell(&docker_compose, &service)?,
+    };
+    let workdir = config.services
+        .get(&service)
+        .and_then(|service| service.working_dir.clone());
+
+    // The exit code of an interactive shell is the one of the last command, so it's not checked
+    docker_compose
+        .exec_cmd(service, user, workdir, vec![shell, String::from("-l")], true)
+        .join()?;
+    Ok(())
+}
+
+/// Returns the first shell of `SHELLS` which is installed in the container
+fn detect_shell(docker_compose: &DockerCompose, service: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let probe = format!(
+        "for shell in {}; do command -v $shell >/dev/null && echo $shell && exit; done",
+        SHELLS.join(" ")
+    );
+    let output = docker_compose
+        .exec_cmd(service.to_string(), None, None, vec![String::from("sh"), String::from("-c"), probe], false)
+        .stdin(subprocess::NullFile)
+        .stderr(subprocess::NullFile)
+        .capture()?;
+
+    if !output.success() {
+        return Err(format!("Could not start a shell in '{}', is the service running?", service).into());
+    }
+
+    let shell = output.stdout_str().trim().to_string();
+    if shell.is_empty() {
+        Ok(String::from("sh"))
+    } else {
+        Ok(shell)
+    }
+}
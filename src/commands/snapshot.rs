@@ -0,0 +1,337 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use bollard::Docker;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use crate::commands::{export_db, import_db};
+use crate::utils::database::{major_minor, parse_version, Compression, Engine};
+use crate::utils::docker_compose::{Config, DockerCompose};
+use crate::utils::general::{format_bytes, get_app_config, get_project_root, print_table, OutputFormat, SnapshotCommand};
+use crate::utils::project_registry::ProjectRegistry;
+
+/// Stored next to every snapshot as `<name>.yml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    pub name: String,
+    pub project: String,
+    pub service: String,
+    pub engine: Engine,
+    pub image: String,
+    /// The version of the database server, if it could be detected
+    pub version: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub size: u64,
+}
+
+/// A project with everything needed to reach its database
+struct Project {
+    name: String,
+    service: String,
+    snapshots_dir: PathBuf,
+    docker_compose: DockerCompose,
+    config: Config,
+}
+
+impl Project {
+    fn load(root: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let app_config = get_app_config(root)?;
+        let docker_compose = DockerCompose::new(root.join("compose.yml"))
+            .with_config_from_docker(app_config.compose_config_from_docker.unwrap_or(false));
+        let config = docker_compose.config()?;
+        let dumps_dir = app_config.dumps_dir.unwrap_or(String::from("dumps"));
+
+        Ok(Project {
+            name: config.name.clone().unwrap_or_default(),
+            service: app_config.database_container.unwrap_or(String::from("db")),
+            snapshots_dir: root.join(dumps_dir).join("snapshots"),
+            docker_compose,
+            config,
+        })
+    }
+
+    fn has_database(&self) -> bool {
+        self.config.services.contains_key(&self.service)
+    }
+
+    fn dump_path(&self, name: &str) -> PathBuf {
+        self.snapshots_dir.join(format!("{}.{}", name, Compression::Zstd.extension()))
+    }
+
+    fn metadata_path(&self, name: &str) -> PathBuf {
+        self.snapshots_dir.join(format!("{}.yml", name))
+    }
+
+    /// The snapshots of the project, oldest first
+    fn snapshots(&self) -> Result<Vec<SnapshotMetadata>, Box<dyn std::error::Error>> {
+        let Ok(entries) = std::fs::read_dir(&self.snapshots_dir) else {
+            return Ok(vec![]);
+        };
+
+        let mut snapshots = vec![];
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.extension().is_some_and(|extension| extension == "yml") {
+                // Other files in the directory don't keep the snapshots from being listed
+                match serde_yaml::from_reader::<_, SnapshotMetadata>(File::open(&path)?) {
+                    Ok(metadata) => snapshots.push(metadata),
+                    Err(error) => eprintln!("Skipping {}, it is not a snapshot ({})", path.display(), error),
+                }
+            }
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.created_at);
+        Ok(snapshots)
+    }
+
+    /// The version of the running database server
+    fn server_version(&self, engine: Engine) -> Option<String> {
+        let output = self.docker_compose
+            .exec_cmd(
+                self.service.clone(),
+                None,
+                None,
+                vec![String::from("sh"), String::from("-c"), engine.version_script().to_string()],
+                false,
+            )
+            .stdin(subprocess::NullFile)
+            .stderr(subprocess::NullFile)
+            .capture()
+            .ok()?;
+        parse_version(&output.stdout_str())
+    }
+}
+
+pub async fn run(docker: &Docker, command: SnapshotCommand) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        SnapshotCommand::Create { name, all } => {
+            let name = name.unwrap_or_else(|| Local::now().format("%Y%m%d-%H%M%S").to_string());
+            validate_name(&name)?;
+
+            let mut failed = 0;
+            for project in projects(all)? {
+                if let Err(error) = create(&project, &name) {
+                    eprintln!("Could not create a snapshot of '{}': {}", project.name, error);
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                return Err(format!("{} snapshot(s) failed", failed).into());
+            }
+        }
+        SnapshotCommand::List { all, format } => {
+            let mut snapshots = vec![];
+            for project in projects(all)? {
+                snapshots.extend(project.snapshots()?);
+            }
+
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&snapshots)?),
+                OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&snapshots)?),
+                OutputFormat::Text => {
+                    if snapshots.is_empty() {
+                        println!("No snapshots found");
+                        return Ok(());
+                    }
+                    let rows: Vec<Vec<String>> = snapshots
+                        .iter()
+                        .map(|snapshot| vec![
+                            snapshot.name.clone(),
+                            snapshot.project.clone(),
+                            snapshot.engine.to_string(),
+                            snapshot.version.clone().unwrap_or_default(),
+                            snapshot.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+                            format_bytes(snapshot.size),
+                        ])
+                        .collect();
+                    print_table(&["NAME", "PROJECT", "ENGINE", "VERSION", "CREATED", "SIZE"], &rows);
+                }
+            }
+        }
+        SnapshotCommand::Restore { name, force } => {
+            if let Some(name) = &name {
+                validate_name(name)?;
+            }
+            let project = Project::load(&get_project_root()?)?;
+            let snapshots = project.snapshots()?;
+            let snapshot = match name {
+                Some(name) => snapshots
+                    .into_iter()
+                    .find(|snapshot| snapshot.name == name)
+                    .ok_or_else(|| format!("There is no snapshot named '{}'", name))?,
+                None => snapshots
+                    .into_iter()
+                    .last()
+                    .ok_or("There are no snapshots of this project")?,
+            };
+
+            let engine = Engine::detect(&project.config, &project.service)?;
+            let image = project.config.services[&project.service].image.clone().unwrap_or_default();
+            let version = project.server_version(engine);
+            if !force {
+                check_compatible(&snapshot, engine, &image, version.as_deref())?;
+            }
+
+            let file = File::open(project.dump_path(&snapshot.name))?;
+            let size = file.metadata()?.len();
+            eprintln!("Restoring snapshot '{}' ...", snapshot.name);
+            import_db::import(docker, &project.name, &project.service, engine, None, true, Box::new(file), Some(size)).await?;
+            println!("Restored snapshot '{}'", snapshot.name);
+        }
+        SnapshotCommand::Delete { name } => {
+            validate_name(&name)?;
+            let project = Project::load(&get_project_root()?)?;
+            let files = [project.dump_path(&name), project.metadata_path(&name)];
+            if !files.iter().any(|file| file.exists()) {
+                return Err(format!("There is no snapshot named '{}'", name).into());
+            }
+            for file in files.iter().filter(|file| file.exists()) {
+                std::fs::remove_file(file)?;
+            }
+            println!("Deleted snapshot '{}'", name);
+        }
+    }
+    Ok(())
+}
+
+/// Names end up in file paths, so they can't leave the snapshots directory
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid snapshot name '{}'", name));
+    }
+    Ok(())
+}
+
+/// The current project, or every project of the registry which has a database
+fn projects(all: bool) -> Result<Vec<Project>, Box<dyn std::error::Error>> {
+    if !all {
+        return Ok(vec![Project::load(&get_project_root()?)?]);
+    }
+
+    let mut projects = vec![];
+    for registered in ProjectRegistry::load()?.projects {
+        if !registered.root.is_dir() {
+            continue;
+        }
+        match Project::load(&registered.root) {
+            Ok(project) if project.has_database() => projects.push(project),
+            Ok(_) => {}
+            Err(error) => eprintln!("Skipping {} ({})", registered.root.display(), error),
+        }
+    }
+    Ok(projects)
+}
+
+/// Creates a snapshot of the project at `project_root`, e.g. before it is deleted
+pub fn create_snapshot(project_root: &Path, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    create(&Project::load(project_root)?, name)
+}
+
+fn create(project: &Project, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let engine = Engine::detect(&project.config, &project.service)?;
+    let path = project.dump_path(name);
+    let metadata_path = project.metadata_path(name);
+    if path.exists() || metadata_path.exists() {
+        return Err(format!("A snapshot named '{}' already exists", name).into());
+    }
+
+    let version = project.server_version(engine);
+    export_db::dump(&project.docker_compose, &project.service, engine, None, Some(&path), Compression::Zstd)?;
+
+    let metadata = SnapshotMetadata {
+        name: name.to_string(),
+        project: project.name.clone(),
+        service: project.service.clone(),
+        engine,
+        image: project.config.services[&project.service].image.clone().unwrap_or_default(),
+        version,
+        created_at: Utc::now(),
+        size: std::fs::metadata(&path)?.len(),
+    };
+    serde_yaml::to_writer(File::create(&metadata_path)?, &metadata)?;
+
+    println!("Created snapshot '{}' of '{}' ({})", name, project.name, format_bytes(metadata.size));
+    Ok(())
+}
+
+/// A dump can be restored into the same server family of the same or a newer version. Without
+/// knowing both versions that can't be told, so `--force` is needed then.
+fn check_compatible(snapshot: &SnapshotMetadata, engine: Engine, image: &str, version: Option<&str>) -> Result<(), String> {
+    if snapshot.engine != engine {
+        return Err(format!(
+            "The snapshot '{}' was taken from {}, but the database is {}",
+            snapshot.name, snapshot.engine, engine
+        ));
+    }
+
+    match (Engine::flavour(&snapshot.image), Engine::flavour(image)) {
+        (Some(snapshot_flavour), Some(flavour)) if snapshot_flavour != flavour => {
+            return Err(format!(
+                "The snapshot '{}' was taken from {}, but the database is {}, use --force to restore it anyway",
+                snapshot.name, snapshot_flavour, flavour
+            ));
+        }
+        (Some(_), Some(_)) => {}
+        _ => {
+            return Err(format!(
+                "Could not tell whether the image {} of the snapshot '{}' matches {}, use --force to restore it anyway",
+                snapshot.image, snapshot.name, image
+            ));
+        }
+    }
+
+    let snapshot_version = snapshot.version.as_deref().and_then(major_minor);
+    let current_version = version.and_then(major_minor);
+    match (snapshot_version, current_version) {
+        (Some(snapshot_version), Some(current_version)) if snapshot_version > current_version => Err(format!(
+            "The snapshot '{}' was taken from {} {}, which is newer than the running {}, use --force to restore it anyway",
+            snapshot.name,
+            engine,
+            snapshot.version.as_deref().unwrap_or_default(),
+            version.unwrap_or_default()
+        )),
+        (Some(_), Some(_)) => Ok(()),
+        _ => Err(format!(
+            "Could not compare the version of the snapshot '{}' ({}) with the running server ({}), use --force to restore it anyway",
+            snapshot.name,
+            snapshot.version.as_deref().unwrap_or("unknown"),
+            version.unwrap_or("unknown")
+        )),
+    }
+}
+
+#[test]
+fn refuse_incompatible_snapshots() {
+    let snapshot = SnapshotMetadata {
+        name: String::from("before-migration"),
+        project: String::from("shop"),
+        service: String::from("db"),
+        engine: Engine::MySql,
+        image: String::from("mariadb:11.2"),
+        version: Some(String::from("11.2.2")),
+        created_at: Utc::now(),
+        size: 0,
+    };
+
+    assert!(check_compatible(&snapshot, Engine::MySql, "mariadb:11.2", Some("11.2.3")).is_ok());
+    assert!(check_compatible(&snapshot, Engine::MySql, "mariadb:11.4", Some("11.4.1")).is_ok());
+    assert!(check_compatible(&snapshot, Engine::MySql, "mariadb:11.2", None).is_err());
+    assert!(check_compatible(&snapshot, Engine::MySql, "mariadb:10.11", Some("10.11.6")).is_err());
+    assert!(check_compatible(&snapshot, Engine::MySql, "registry.local/db:1", Some("11.2.3")).is_err());
+    assert!(check_compatible(&snapshot, Engine::Postgres, "postgres:16", Some("16.1")).is_err());
+
+    // MySQL and MariaDB share an engine, but not their dumps
+    let mysql_snapshot = SnapshotMetadata {
+        image: String::from("mysql:8.4"),
+        version: Some(String::from("8.4.0")),
+        ..snapshot.clone()
+    };
+    assert!(check_compatible(&mysql_snapshot, Engine::MySql, "mariadb:11.4", Some("11.4.1")).is_err());
+    assert!(check_compatible(&mysql_snapshot, Engine::MySql, "percona:8.4", Some("8.4.1")).is_ok());
+}
+
+#[test]
+fn refuse_snapshot_names_outside_the_directory() {
+    assert!(validate_name("before-migration").is_ok());
+    assert!(validate_name("../../compose").is_err());
+    assert!(validate_name("..").is_err());
+    assert!(validate_name("a\\b").is_err());
+    assert!(validate_name("").is_err());
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use bollard::container::{InspectContainerOptions, ListContainersOptions};
+use bollard::Docker;
+use serde::Serialize;
+use crate::utils::docker_compose::Config;
+use crate::utils::general::{format_duration, print_table, OutputFormat};
+use crate::utils::traefik;
+
+/// The state of a single service of a project, or of one of its replicas
+#[derive(Debug, Serialize)]
+pub struct ServiceStatus {
+    pub service: String,
+    pub container: Option<String>,
+    pub state: String,
+    pub health: Option<String>,
+    pub started_at: Option<String>,
+    pub uptime: Option<String>,
+    pub ports: Vec<String>,
+    pub hostnames: Vec<String>,
+}
+
+pub async fn run(docker: &Docker, config: &Config, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let project = config.name.clone().unwrap_or_default();
+    let statuses = service_statuses(docker, &project, Some(config)).await?;
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&statuses)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&statuses)?),
+        OutputFormat::Text => {
+            let rows: Vec<Vec<String>> = statuses
+                .iter()
+                .map(|status| vec![
+                    status.service.clone(),
+                    status.container.clone().unwrap_or_default(),
+                    status.state.clone(),
+                    status.health.clone().unwrap_or_default(),
+                    status.uptime.clone().unwrap_or_default(),
+                    status.ports.join(", "),
+                    status.hostnames.join(", "),
+                ])
+                .collect();
+            print_table(&["SERVICE", "CONTAINER", "STATE", "HEALTH", "UPTIME", "PORTS", "HOSTNAME"], &rows);
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the state of all containers of a compose project. Services of the given config
+/// without a container are reported as `not created`.
+pub async fn service_statuses(
+    docker: &Docker,
+    project: &str,
+    config: Option<&Config>,
+) -> Result<Vec<ServiceStatus>, bollard::errors::Error> {
+    let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+        all: true,
+        filters: HashMap::from([(
+            String::from("label"),
+            vec![format!("com.docker.compose.project={}", project)],
+        )]),
+        ..Default::default()
+    })).await?;
+
+    let mut statuses = vec![];
+    for container in containers {
+        let labels = container.labels.unwrap_or_default();
+        let name = container.names
+            .and_then(|names| names.first().cloned())
+            .map(|name| name.trim_start_matches('/').to_string());
+
+        let state = match &container.id {
+            Some(id) => docker
+                .inspect_container(id, None::<InspectContainerOptions>)
+                .await?
+                .state,
+            None => None,
+        };
+        let health = state
+            .as_ref()
+            .and_then(|state| state.health.as_ref())
+            .and_then(|health| health.status)
+            .map(|status| status.to_string())
+            .filter(|status| !status.is_empty() && status != "none");
+        let is_running = container.state.as_deref() == Some("running");
+        let started_at = state
+            .and_then(|state| state.started_at)
+            .filter(|_| is_running);
+        let uptime = started_at
+            .as_ref()
+            .and_then(|started_at| chrono::DateTime::parse_from_rfc3339(started_at).ok())
+            .map(|started_at| format_duration(chrono::Utc::now().signed_duration_since(started_at)));
+
+        let mut ports: Vec<String> = container.ports
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|port| {
+                let protocol = port.typ.map(|typ| typ.to_string()).unwrap_or_default();
+                port.public_port
+                    .map(|public_port| format!("{}->{}/{}", public_port, port.private_port, protocol))
+            })
+            .collect();
+        // Docker lists a published port once for IPv4 and once for IPv6
+        ports.sort();
+        ports.dedup();
+
+        statuses.push(ServiceStatus {
+            service: labels.get("com.docker.compose.service").cloned().unwrap_or_default(),
+            container: name,
+            state: container.state.unwrap_or_default(),
+            health,
+            started_at,
+            uptime,
+            ports,
+            hostnames: hostnames(&labels),
+        });
+    }
+
+    if let Some(config) = config {
+        for service in config.services.keys() {
+            if !statuses.iter().any(|status| &status.service == service) {
+                statuses.push(ServiceStatus {
+                    service: service.clone(),
+                    container: None,
+                    state: String::from("not created"),
+                    health: None,
+                    started_at: None,
+                    uptime: None,
+                    ports: vec![],
+                    hostnames: vec![],
+                });
+            }
+        }
+    }
+
+    statuses.sort_by(|a, b| (&a.service, &a.container).cmp(&(&b.service, &b.container)));
+    Ok(statuses)
+}
+
+fn hostnames(labels: &HashMap<String, String>) -> Vec<String> {
+    let mut hostnames: Vec<String> = traefik::routers(labels)
+        .into_iter()
+        .flat_map(|router| router.hosts)
+        .collect();
+    hostnames.sort();
+    hostnames.dedup();
+    hostnames
+}
@@ -5,8 +5,11 @@ mod commands;
 mod utils;
 
 use bollard::Docker;
-use clap::Parser;
-use utils::general::{ensure_proxy_running, get_app_config, get_project_root};
+use clap::{CommandFactory, Parser};
+use utils::general::{get_app_config, get_project_root};
+use utils::project_registry;
+use utils::certs;
+use utils::proxy::ensure_proxy_running;
 use std::path::{PathBuf, Path};
 use crate::utils::general::{Cli, Commands, is_docker_required, docker_running, check_and_setup_system, check_and_setup_docker};
 
@@ -44,6 +47,10 @@ lazy_static! {
         .iter()
         .collect()
     };
+    // Files dev-cli manages itself, like the extracted proxy stack
+    static ref DATA_DIR: PathBuf = dirs::data_dir().unwrap().join("dev-cli");
+    // Every project that ran through dev-cli, stored next to the global config
+    static ref PROJECT_REGISTRY_PATH: PathBuf = CONFIG_FILE_PATH_GLOBAL.with_file_name("projects.yml");
 }
 
 #[tokio::main]
@@ -63,33 +70,97 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
         check_and_setup_docker(&docker).await;
     }
 
-    println! {"Global config at {}", CONFIG_FILE_PATH_GLOBAL.clone().into_os_string().into_string().unwrap()};
+    use Commands::*;
+
+    // Commands which don't belong to a project
+    match cli.command {
+        Some(Cert { command }) => {
+            commands::cert::run(command)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        // Checks for Docker itself, the files can be cleaned without it
+        Some(Clean { items, all, yes }) => {
+            commands::clean::run(&docker, items, all, yes).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Completion { shell }) => {
+            commands::completion::run(shell)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(CompleteValues { kind }) => {
+            commands::completion::values(kind);
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Config { command }) => {
+            commands::config::run(command)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(GlobalStatus { format }) => {
+            commands::global_status::run(&docker, format).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(List { running, stale, format }) => {
+            commands::list::run(&docker, running, stale, format).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Init { template, name, tld, force, list }) => {
+            commands::init::run(template, name, tld, force, list)?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        Some(Poweroff { parallel }) => {
+            commands::poweroff::run(&docker, parallel).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        // Works on other projects with --all, so it loads the projects itself
+        Some(Snapshot { command }) => {
+            commands::snapshot::run(&docker, command).await?;
+            return Ok(sysexits::ExitCode::Ok);
+        }
+        _ => {}
+    }
 
     // Find .dev-cli.yml/.dev-cli.dist.yml in the current directory or any
     // parent directory to determine the project root
     let project_root = get_project_root()?;
-    let app_config = get_app_config(&project_root);
-    match app_config {
-        Ok(conf) => println!("config loaded: {:?}", conf),
-        Err(e) => eprintln!("error loading app config: {:?}", e)
-    }
+    let app_config = match get_app_config(&project_root) {
+        Ok(conf) => conf,
+        Err(e) => {
+            eprintln!("error loading app config: {:?}", e);
+            sysexits::ExitCode::Config.exit()
+        }
+    };
 
     // Find and read the docker `compose.yml` file
     // TODO: Check if command requires knowledge of the compose config
     let docker_compose_config_path = Path::new(project_root.as_ref()).join("compose.yml");
     let docker_compose = if docker_compose_config_path.is_file() {
         utils::docker_compose::DockerCompose::new(docker_compose_config_path)
+            .with_config_from_docker(app_config.compose_config_from_docker.unwrap_or(false))
     } else {
-        println!(
+        eprintln!(
             "Could not find a docker compose file in the project root ({})",
             docker_compose_config_path.display()
         );
         sysexits::ExitCode::OsErr.exit()
     };
-    let _docker_compose_config = match docker_compose.config() {
+    // The override file follows the `addons` in the config, also when it was edited by hand. Only the
+    // commands running compose write it, the others just warn about a broken list.
+    let runs_compose = matches!(
+        cli.command,
+        None | Some(Delete { .. } | Service { .. } | Exec { .. } | Start | ExportDb { .. } | Restart { .. } | Run { .. } | Shell { .. } | Stop { .. })
+    );
+    if runs_compose {
+        if let Err(error) = utils::addons::write_override(&project_root, &app_config) {
+            eprintln!("Could not generate the compose file of the add-ons ({:#})", error);
+            sysexits::ExitCode::Config.exit()
+        }
+    } else if let Err(error) = utils::addons::render_override(&app_config) {
+        eprintln!("The add-ons are left out ({:#})", error);
+    }
+    let docker_compose_config = match docker_compose.config() {
         Ok(config) => config,
         Err(error) => {
-            println!("Could not read the docker compose file ({})", error);
+            eprintln!("Could not read the docker compose file ({:#})", error);
             sysexits::ExitCode::OsErr.exit()
         }
     };
@@ -102,17 +173,66 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
     //    println!("-> {:?}", image.id);
     //}
 
-    use Commands::*;
     match cli.command {
         Some(command) => {
             match command {
+                Delete { yes, snapshot } => {
+                    commands::delete::run(docker_compose, &docker_compose_config, &project_root, yes, snapshot)?
+                }
+                Describe { format } => {
+                    commands::describe::run(&docker, &project_root, &app_config, &docker_compose_config, format).await?
+                }
+                Service { command } => {
+                    commands::service::run(&docker, docker_compose, &docker_compose_config, &app_config, &project_root, command).await?
+                }
+                Hostname { command } => {
+                    commands::hostname::run(&docker, &app_config, &docker_compose_config, command).await?
+                }
                 Exec { service, user, command } => {
                     commands::exec::run(docker_compose, service, user, command.to_vec())?
                 }
                 Start => {
                     println!("Starting project ...");
-                    ensure_proxy_running();
-                    docker_compose.up(None, true)?
+                    ensure_proxy_running(&docker, app_config.dns_container.unwrap_or(true)).await?;
+                    certs::ensure_project_certificate(&project_root, &docker_compose_config)?;
+                    docker_compose.up(None, true)?;
+                    project_registry::record_started(&project_root, docker_compose_config.name.as_deref().unwrap_or_default())?
+                }
+                ExportDb { database, output, compress } => {
+                    commands::export_db::run(docker_compose, &docker_compose_config, &app_config, &project_root, database, output, compress)?
+                }
+                ImportDb { file, database, recreate } => {
+                    commands::import_db::run(&docker, &docker_compose_config, &app_config, &project_root, file, database, recreate).await?
+                }
+                ImportFiles { source, target, delete } => {
+                    commands::import_files::run(&docker, &docker_compose_config, &app_config, source, target, delete).await?
+                }
+                Launch { service, print } => {
+                    commands::launch::run(&docker_compose_config, service, print)?
+                }
+                Logs { services, follow, since, tail, grep } => {
+                    commands::logs::run(&docker, &docker_compose_config, services, follow, since, tail, grep).await?
+                }
+                Restart { remove_data, rebuild, services } => {
+                    println!("Restarting project ...");
+                    let services = docker_compose_config.select_services(&services)?;
+                    docker_compose.down(services.clone(), remove_data)?;
+                    if rebuild {
+                        docker_compose.build(services.clone())?;
+                    }
+                    ensure_proxy_running(&docker, app_config.dns_container.unwrap_or(true)).await?;
+                    certs::ensure_project_certificate(&project_root, &docker_compose_config)?;
+                    docker_compose.up(services, true)?;
+                    project_registry::record_started(&project_root, docker_compose_config.name.as_deref().unwrap_or_default())?
+                }
+                Run { command } => {
+                    commands::run::run(docker_compose, &app_config, command)?
+                }
+                Shell { service, user } => {
+                    commands::shell::run(docker_compose, &docker_compose_config, &app_config, service, user)?
+                }
+                Status { format } => {
+                    commands::status::run(&docker, &docker_compose_config, format).await?
                 }
                 Stop { remove_data } => {
                     if remove_data {
@@ -128,6 +248,10 @@ async fn main() -> Result<sysexits::ExitCode, Box<dyn std::error::Error>> {
                 }
             }
         }
+        // Without a command there is nothing to exec, the default service may not even exist
+        None if cli.exec_command.is_empty() => {
+            Cli::command().print_help()?;
+        }
         None => {
             commands::exec::run(docker_compose, cli.service.to_owned(), None, cli.exec_command)?;
         }
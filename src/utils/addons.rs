@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::DATA_DIR;
+use super::app_config::AppConfig;
+use super::general::{Asset, DEV_CLI_NETWORK};
+
+/// An extra service from the embedded catalogue in `files/addons`
+#[derive(Debug, Clone)]
+pub struct Addon {
+    pub name: String,
+    pub description: String,
+    /// The compose fragment, `{{ database_container }}` is replaced when the override is generated
+    fragment: String,
+}
+
+impl Addon {
+    /// The services the add-on adds to the project
+    pub fn services(&self) -> Vec<String> {
+        serde_yaml::from_str::<Value>(&self.fragment)
+            .ok()
+            .and_then(|fragment| fragment.get("services").and_then(Value::as_mapping).cloned())
+            .map(|services| services.keys().filter_map(Value::as_str).map(String::from).collect())
+            .unwrap_or_default()
+    }
+
+    fn render(&self, app_config: &AppConfig) -> Result<Mapping> {
+        let database_container = app_config.database_container.as_deref().unwrap_or("db");
+        let fragment = self.fragment.replace("{{ database_container }}", database_container);
+        let mut fragment: Mapping = serde_yaml::from_str(&fragment)
+            .with_context(|| format!("Invalid compose fragment of the add-on '{}'", self.name))?;
+        fragment.remove("x-description");
+        Ok(fragment)
+    }
+}
+
+/// All add-ons of the catalogue, sorted by name
+pub fn catalogue() -> Vec<Addon> {
+    let mut addons: Vec<Addon> = Asset::iter()
+        .filter_map(|file| {
+            let name = file.strip_prefix("addons/")?.strip_suffix(".yml")?.to_string();
+            let fragment = String::from_utf8_lossy(&Asset::get(&file)?.data).to_string();
+            let description = fragment
+                .lines()
+                .find_map(|line| line.strip_prefix("x-description:"))
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            Some(Addon { name, description, fragment })
+        })
+        .collect();
+    addons.sort_by(|a, b| a.name.cmp(&b.name));
+    addons
+}
+
+pub fn get(name: &str) -> Option<Addon> {
+    catalogue().into_iter().find(|addon| addon.name == name)
+}
+
+/// Where the override files of all projects are kept
+pub fn override_dir() -> PathBuf {
+    DATA_DIR.join("addons")
+}
+
+/// The generated compose file with the enabled add-ons of a project. It is kept out of the project,
+/// so it can't end up in its repository, and named after the path of the project root.
+pub fn override_file(project_root: &Path) -> PathBuf {
+    let root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+    let name: String = root
+        .to_string_lossy()
+        .chars()
+        .map(|char| if char.is_ascii_alphanumeric() || char == '-' || char == '.' { char } else { '_' })
+        .collect();
+    override_dir().join(format!("{}.yml", name.trim_start_matches('_')))
+}
+
+/// The content of the override file for the `addons` in the config, `None` if none are enabled
+pub fn render_override(app_config: &AppConfig) -> Result<Option<String>> {
+    let enabled = app_config.addons.clone().unwrap_or_default();
+    if enabled.is_empty() {
+        return Ok(None);
+    }
+
+    let mut services = Mapping::new();
+    let mut volumes = Mapping::new();
+    for name in enabled {
+        let addon = get(&name).ok_or_else(|| anyhow!("Unknown add-on '{}' in the config", name))?;
+        let fragment = addon.render(app_config)?;
+        for (key, target) in [("services", &mut services), ("volumes", &mut volumes)] {
+            if let Some(Value::Mapping(mapping)) = fragment.get(key) {
+                target.extend(mapping.clone());
+            }
+        }
+    }
+
+    let mut document = Mapping::new();
+    document.insert(Value::from("services"), Value::Mapping(services));
+    if !volumes.is_empty() {
+        document.insert(Value::from("volumes"), Value::Mapping(volumes));
+    }
+    // The add-ons with a web interface are routed by Traefik
+    let mut network = Mapping::new();
+    network.insert(Value::from("external"), Value::from(true));
+    let mut networks = Mapping::new();
+    networks.insert(Value::from(DEV_CLI_NETWORK), Value::Mapping(network));
+    document.insert(Value::from("networks"), Value::Mapping(networks));
+
+    Ok(Some(format!(
+        "# Generated by dev-cli from the `addons` in the config, changes are overwritten\n{}",
+        serde_yaml::to_string(&document)?
+    )))
+}
+
+/// Writes the override file for the enabled add-ons, or removes it if there are none
+pub fn write_override(project_root: &Path, app_config: &AppConfig) -> Result<()> {
+    let file = override_file(project_root);
+    match render_override(app_config)? {
+        // Unchanged files are left alone, so their modification time means something
+        Some(content) if std::fs::read_to_string(&file).ok().as_deref() != Some(content.as_str()) => {
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&file, content).with_context(|| format!("Could not write {}", file.display()))?;
+        }
+        None if file.is_file() => {
+            std::fs::remove_file(&file).with_context(|| format!("Could not remove {}", file.display()))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[test]
+fn render_override_for_enabled_addons() -> Result<(), Box<dyn std::error::Error>> {
+    let app_config = AppConfig {
+        database_container: Some(String::from("mysql")),
+        addons: Some(vec![String::from("redis"), String::from("adminer")]),
+        ..Default::default()
+    };
+
+    let content = render_override(&app_config)?.unwrap();
+    let document: Value = serde_yaml::from_str(&content)?;
+    let services: Vec<&str> = document["services"].as_mapping().unwrap().keys().filter_map(Value::as_str).collect();
+    assert_eq!(services, ["redis", "adminer"]);
+    assert_eq!(document["services"]["adminer"]["environment"]["ADMINER_DEFAULT_SERVER"], "mysql");
+    assert!(document["volumes"].get("redis-data").is_some());
+    assert_eq!(document["networks"][DEV_CLI_NETWORK]["external"], true);
+    assert!(document.get("x-description").is_none());
+
+    assert!(render_override(&AppConfig { addons: Some(vec![]), ..Default::default() })?.is_none());
+    assert!(render_override(&AppConfig { addons: Some(vec![String::from("nope")]), ..Default::default() }).is_err());
+    Ok(())
+}
@@ -1,50 +1,212 @@
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use serde_merge::omerge;
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::{CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT, CONFIG_FILE_PATH_GLOBAL};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub database_container: Option<String>,
     pub dumps_dir: Option<String>,
+    /// Read the compose config with `docker compose config` instead of parsing the files natively
+    pub compose_config_from_docker: Option<bool>,
+    /// Named command recipes which can be executed with `dev-cli run <name>`
+    #[serde(rename = "run-commands")]
+    pub run_commands: Option<BTreeMap<String, RunCommand>>,
+    /// Settings per compose service, e.g. the shell used by `dev-cli shell`
+    pub services: Option<BTreeMap<String, ServiceConfig>>,
+    /// Resolve the hostnames of the projects with the DNS container of the proxy. If disabled, it
+    /// isn't started and `dev-cli hostname` writes them to the hosts file instead.
+    pub dns_container: Option<bool>,
+    /// Directories `dev-cli list` searches for projects which are not known from being started
+    pub workspaces: Option<Vec<String>>,
+    /// Directories with files uploaded by the users of the project, which `dev-cli import-files` fills
+    pub upload_dirs: Option<Vec<UploadDir>>,
+    /// Extra services from the catalogue of dev-cli, see `dev-cli service list`
+    pub addons: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadDir {
+    pub service: String,
+    /// The directory in the container, it has to be in a bind mount or a named volume
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    /// The shell for `dev-cli shell`, detected from the container if omitted
+    pub shell: Option<String>,
+    /// The user for `dev-cli shell` if none is given on the command line
+    pub user: Option<String>,
+}
+
+/// A named list of commands, executed one after the other or all at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCommand {
+    #[serde(default)]
+    pub parallel: bool,
+    pub commands: Vec<RunCommandStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCommandStep {
+    /// The service to run the command in. If omitted, the first service in the project will be used.
+    pub container: Option<String>,
+    pub user: Option<String>,
+    /// Passed to `sh -c` inside the container, so globs and pipes work as expected
+    pub command: String,
 }
 
 impl std::default::Default for AppConfig {
     fn default() -> Self {
         AppConfig {
             database_container: Some(String::from("db")),
-            dumps_dir: Some(String::from("dumps"))
+            dumps_dir: Some(String::from("dumps")),
+            compose_config_from_docker: Some(false),
+            run_commands: None,
+            services: None,
+            dns_container: Some(true),
+            workspaces: None,
+            upload_dirs: None,
+            addons: None,
+        }
+    }
+}
+
+/// The places a config value can come from, later layers override earlier ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigLayer {
+    Default,
+    Global,
+    Dist,
+    Local,
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigLayer::Default => write!(f, "default"),
+            ConfigLayer::Global => write!(f, "global"),
+            ConfigLayer::Dist => write!(f, "dist"),
+            ConfigLayer::Local => write!(f, "local"),
         }
     }
 }
 
+/// The config files in the order they are merged, the project files are left out without a project root
+pub fn config_files(project_root: Option<&Path>) -> Vec<(ConfigLayer, PathBuf)> {
+    let mut files = vec![(ConfigLayer::Global, CONFIG_FILE_PATH_GLOBAL.clone())];
+    if let Some(project_root) = project_root {
+        files.push((ConfigLayer::Dist, project_root.join(CONFIG_FILE_NAME_PROJECT)));
+        files.push((ConfigLayer::Local, project_root.join(CONFIG_FILE_NAME_LOCAL)));
+    }
+    files
+}
+
+/// Parses a config file and collects the keys which are not part of `AppConfig`, e.g. `services.php.shel`
+pub fn parse_with_unknown_keys(content: &str) -> Result<(Option<AppConfig>, Vec<String>)> {
+    let mut unknown_keys = vec![];
+    let deserializer = serde_yaml::Deserializer::from_str(content);
+    let config: Option<AppConfig> = serde_ignored::deserialize(deserializer, |path| {
+        // `?` stands for an `Option` in the path and means nothing to the user
+        let path = path.to_string();
+        let keys: Vec<&str> = path.split('.').filter(|key| *key != "?").collect();
+        unknown_keys.push(keys.join("."));
+    })?;
+    Ok((config, unknown_keys))
+}
+
 impl AppConfig {
+    /// Merges the default, global, project (dist) and local config, in that order
     pub fn merge_from_project_root(
         project_root: impl Into<PathBuf>
     ) -> Result<Self> {
-        let project_root = project_root.into();
-        let mut config_file = project_root.clone();
-        config_file.push(".dev-cli.yml");
-        println!("config file: {:?}", &config_file);
-        let mut dist_config_file = project_root.clone();
-        dist_config_file.push("../../.dev-cli.dist.yml");
-        println!("dist config file: {:?}", &dist_config_file);
-
-        let config: AppConfig
-            = serde_yaml::from_reader(File::open(config_file)?)?;
-        println!("config: {:?}", &config);
-        let dist_config: AppConfig
-            = serde_yaml::from_reader(File::open(dist_config_file)?)?;
-        println!("dist config: {:?}", &dist_config);
-        let default_config = AppConfig::default();
-        println!("default config: {:?}", &default_config);
-
-        let merge_result  = omerge::<AppConfig, AppConfig, AppConfig>(
-            default_config,
-            omerge::<AppConfig, AppConfig, AppConfig>(dist_config, config)?,
-        )?;
+        Self::merge_files(Some(&project_root.into()))
+    }
+
+    /// The default config merged with the global one, for commands outside of a project
+    pub fn global() -> Result<Self> {
+        Self::merge_files(None)
+    }
+
+    fn merge_files(project_root: Option<&Path>) -> Result<Self> {
+        let mut merge_result = AppConfig::default();
+        for (_, config_file) in config_files(project_root) {
+            if let Some(config) = Self::from_file(&config_file)? {
+                merge_result = merge_result.merge(config)?;
+            }
+        }
+
+        Ok(merge_result)
+    }
+
+    pub fn from_file(config_file: &Path) -> Result<Option<Self>> {
+        if !config_file.is_file() {
+            return Ok(None);
+        }
+
+        // An empty file is valid and results in `None`
+        let config: Option<AppConfig> = serde_yaml::from_reader(File::open(config_file)?)
+            .with_context(|| format!("Could not parse {}", config_file.display()))?;
+
+        Ok(config)
+    }
+
+    fn merge(self, other: AppConfig) -> Result<Self> {
+        let mut run_commands = self.run_commands.clone().unwrap_or_default();
+        let other_run_commands = other.run_commands.clone();
+        let mut services = self.services.clone().unwrap_or_default();
+        let other_services = other.services.clone();
+
+        let mut merge_result = omerge::<AppConfig, AppConfig, AppConfig>(self, other)?;
+
+        // Recipes are merged by name, so a local config can replace a single recipe of the project
+        if let Some(other_run_commands) = other_run_commands {
+            run_commands.extend(other_run_commands);
+            merge_result.run_commands = Some(run_commands);
+        }
+
+        // Service settings are merged per setting, e.g. a local config can set only the user
+        if let Some(other_services) = other_services {
+            for (name, other_service) in other_services {
+                let service = match services.remove(&name) {
+                    Some(service) => omerge::<ServiceConfig, ServiceConfig, ServiceConfig>(service, other_service)?,
+                    None => other_service,
+                };
+                services.insert(name, service);
+            }
+            merge_result.services = Some(services);
+        }
 
         Ok(merge_result)
     }
 }
+
+#[test]
+fn merge_run_commands_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    // Merged in memory, the global config of the machine running the test must not leak in
+    let dist: AppConfig = serde_yaml::from_str(
+        "dumps_dir: backups\n\
+         run-commands:\n  \
+           dev:\n    commands:\n      - {container: node, command: yarn run dev}\n  \
+           update:\n    parallel: true\n    commands:\n      - {container: node, command: yarn upgrade}\n",
+    )?;
+    let local: AppConfig = serde_yaml::from_str(
+        "run-commands:\n  \
+           dev:\n    commands:\n      - {container: node, user: node, command: yarn run watch}\n",
+    )?;
+
+    let config = AppConfig::default().merge(dist)?.merge(local)?;
+    let run_commands = config.run_commands.unwrap();
+
+    assert_eq!(config.dumps_dir.as_deref(), Some("backups"));
+    assert_eq!(config.database_container.as_deref(), Some("db"));
+    assert_eq!(run_commands["dev"].commands[0].command, "yarn run watch");
+    assert!(run_commands["update"].parallel);
+
+    Ok(())
+}
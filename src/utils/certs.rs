@@ -0,0 +1,302 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use rcgen::{
+    date_time_ymd, BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose,
+};
+use serde::Serialize;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+
+use crate::DATA_DIR;
+use super::compose_loader;
+use super::docker_compose::Config;
+use super::traefik;
+
+/// The certificate Traefik serves for hostnames without one of their own, like its dashboard
+pub const DEFAULT_CERTIFICATE: &str = "dev-cli-default";
+const DEFAULT_HOSTS: [&str; 2] = ["localhost", "traefik.test"];
+
+const CA_NAME: &str = "dev-cli local development CA";
+const CA_VALIDITY_DAYS: u64 = 10 * 365;
+/// Browsers refuse server certificates which are valid for longer than 398 days
+const CERTIFICATE_VALIDITY_DAYS: u64 = 397;
+/// Certificates are issued again when they expire in less than this
+const RENEW_BEFORE_DAYS: i64 = 30;
+
+/// The local CA lives outside of the directories mounted into the proxy, Traefik never needs its key
+pub fn ca_dir() -> PathBuf {
+    DATA_DIR.join("ca")
+}
+
+pub fn ca_certificate_path() -> PathBuf {
+    ca_dir().join("rootCA.pem")
+}
+
+fn ca_key_path() -> PathBuf {
+    ca_dir().join("rootCA.key.pem")
+}
+
+/// Mounted into Traefik as `/certs/<name>.pem`
+fn certificate_path(name: &str) -> PathBuf {
+    DATA_DIR.join("certs").join(format!("{}.pem", name))
+}
+
+fn key_path(name: &str) -> PathBuf {
+    DATA_DIR.join("certs").join(format!("{}.key.pem", name))
+}
+
+/// The dynamic config of the file provider, watched by Traefik in `/etc/traefik/certs-conf`
+fn tls_config_path(name: &str) -> PathBuf {
+    DATA_DIR.join("certs-conf").join(format!("{}.yml", name))
+}
+
+/// A certificate issued by dev-cli
+#[derive(Debug, Clone, Serialize)]
+pub struct CertificateInfo {
+    pub name: String,
+    pub hosts: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    /// False if the CA was created again since, browsers which trust the new one won't accept it
+    pub issued_by_ca: bool,
+}
+
+impl CertificateInfo {
+    pub fn expires_soon(&self) -> bool {
+        self.expires_at - Utc::now() < chrono::Duration::days(RENEW_BEFORE_DAYS)
+    }
+
+    fn is_valid_for(&self, hosts: &[String]) -> bool {
+        self.issued_by_ca && !self.expires_soon() && hosts.iter().all(|host| self.hosts.contains(host))
+    }
+}
+
+/// The local CA, created the first time it is needed
+struct Ca {
+    /// Only used to sign, it is created again from the stored key with the same name
+    certificate: Certificate,
+    key: KeyPair,
+}
+
+impl Ca {
+    fn load_or_create() -> Result<Self> {
+        let key = if ca_key_path().is_file() && ca_certificate_path().is_file() {
+            KeyPair::from_pem(&fs::read_to_string(ca_key_path())?)
+                .with_context(|| format!("Could not read the key of the CA ({})", ca_key_path().display()))?
+        } else {
+            let key = KeyPair::generate()?;
+            let certificate = Self::params().self_signed(&key)?;
+            fs::create_dir_all(ca_dir())?;
+            write_private(&ca_key_path(), &key.serialize_pem())?;
+            fs::write(ca_certificate_path(), certificate.pem())?;
+            println!("Created the local CA, run `dev-cli cert trust` to trust it");
+            key
+        };
+
+        Ok(Ca { certificate: Self::params().self_signed(&key)?, key })
+    }
+
+    fn params() -> CertificateParams {
+        let mut params = CertificateParams::default();
+        params.distinguished_name.remove(DnType::CommonName);
+        params.distinguished_name.push(DnType::CommonName, CA_NAME);
+        params.distinguished_name.push(DnType::OrganizationName, "dev-cli");
+        params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        set_validity(&mut params, CA_VALIDITY_DAYS);
+        params
+    }
+}
+
+/// The hostnames of the Traefik routers of a project and a wildcard for its subdomains at the
+/// configured TLD (`TLD` in the `.env` of the project, `test` by default)
+pub fn project_hosts(project_root: &Path, config: &Config) -> Vec<String> {
+    let project = config.name.clone().unwrap_or_default();
+    let tld = fs::read_to_string(project_root.join(".env"))
+        .ok()
+        .and_then(|content| compose_loader::parse_env(&content).ok())
+        .and_then(|variables| variables.get("TLD").cloned())
+        .unwrap_or(String::from("test"));
+
+    let mut hosts = vec![format!("{}.{}", project, tld), format!("*.{}.{}", project, tld)];
+    for host in traefik::service_routers(config).into_values().flatten().flat_map(|router| router.hosts) {
+        if !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    hosts
+}
+
+/// Issues the certificate of a project unless a valid one for all hostnames exists already
+pub fn ensure_project_certificate(project_root: &Path, config: &Config) -> Result<()> {
+    let name = config.name.clone().unwrap_or_default();
+    ensure_certificate(&name, &project_hosts(project_root, config))
+}
+
+/// Issues the certificate Traefik falls back to, unless a valid one exists already
+pub fn ensure_default_certificate() -> Result<()> {
+    let hosts: Vec<String> = DEFAULT_HOSTS.iter().map(|host| host.to_string()).collect();
+    ensure_certificate(DEFAULT_CERTIFICATE, &hosts)
+}
+
+fn ensure_certificate(name: &str, hosts: &[String]) -> Result<()> {
+    if read_certificate(name)?.is_some_and(|certificate| certificate.is_valid_for(hosts)) {
+        return Ok(());
+    }
+    issue(name, hosts)
+}
+
+/// Issues a certificate signed by the local CA and writes the Traefik config which serves it
+pub fn issue(name: &str, hosts: &[String]) -> Result<()> {
+    let ca = Ca::load_or_create()?;
+
+    let mut params = CertificateParams::new(hosts.to_vec())?;
+    params.distinguished_name.remove(DnType::CommonName);
+    params.distinguished_name.push(DnType::CommonName, hosts.first().cloned().unwrap_or_default());
+    params.distinguished_name.push(DnType::OrganizationName, "dev-cli");
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature, KeyUsagePurpose::KeyEncipherment];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.use_authority_key_identifier_extension = true;
+    set_validity(&mut params, CERTIFICATE_VALIDITY_DAYS);
+
+    let key = KeyPair::generate()?;
+    let certificate = params.signed_by(&key, &ca.certificate, &ca.key)?;
+
+    fs::create_dir_all(DATA_DIR.join("certs"))?;
+    fs::create_dir_all(DATA_DIR.join("certs-conf"))?;
+    write_private(&key_path(name), &key.serialize_pem())?;
+    fs::write(certificate_path(name), certificate.pem())?;
+    fs::write(tls_config_path(name), tls_config(name))?;
+
+    println!("Issued a certificate for {}", hosts.join(", "));
+    Ok(())
+}
+
+/// The file provider config for a certificate, the default one becomes the fallback of Traefik
+fn tls_config(name: &str) -> String {
+    if name == DEFAULT_CERTIFICATE {
+        format!(
+            "# Generated by dev-cli\n\
+             tls:\n  \
+               options:\n    \
+                 tls-opts:\n      \
+                   minVersion: VersionTLS12\n  \
+               stores:\n    \
+                 default:\n      \
+                   defaultCertificate:\n        \
+                     certFile: /certs/{name}.pem\n        \
+                     keyFile: /certs/{name}.key.pem\n",
+        )
+    } else {
+        format!(
+            "# Generated by dev-cli\n\
+             tls:\n  \
+               certificates:\n    \
+                 - certFile: /certs/{name}.pem\n      \
+                   keyFile: /certs/{name}.key.pem\n",
+        )
+    }
+}
+
+/// The certificates issued by dev-cli, sorted by name
+pub fn list() -> Result<Vec<CertificateInfo>> {
+    let Ok(entries) = fs::read_dir(DATA_DIR.join("certs")) else {
+        return Ok(vec![]);
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().map(String::from))
+        .filter(|file| !file.ends_with(".key.pem"))
+        .filter_map(|file| file.strip_suffix(".pem").map(String::from))
+        .collect();
+    names.sort();
+
+    let mut certificates = vec![];
+    for name in names {
+        certificates.extend(read_certificate(&name)?);
+    }
+    Ok(certificates)
+}
+
+pub fn read_certificate(name: &str) -> Result<Option<CertificateInfo>> {
+    let path = certificate_path(name);
+    if !path.is_file() || !key_path(name).is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read(&path)?;
+    let (_, pem) = x509_parser::pem::parse_x509_pem(&content)
+        .map_err(|error| anyhow!("Could not read {} ({})", path.display(), error))?;
+    let certificate = pem.parse_x509()
+        .map_err(|error| anyhow!("Could not read {} ({})", path.display(), error))?;
+
+    let hosts = certificate.subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|extension| {
+            extension.value.general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(host) => Some(host.to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let authority_key = certificate.iter_extensions().find_map(|extension| match extension.parsed_extension() {
+        ParsedExtension::AuthorityKeyIdentifier(identifier) => identifier.key_identifier.as_ref().map(|key| key.0.to_vec()),
+        _ => None,
+    });
+
+    Ok(Some(CertificateInfo {
+        name: name.to_string(),
+        hosts,
+        expires_at: DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0).unwrap_or_default(),
+        issued_by_ca: authority_key.is_some() && authority_key == ca_key_identifier(),
+    }))
+}
+
+/// The subject key identifier of the stored CA, which the certificates it issued refer to
+fn ca_key_identifier() -> Option<Vec<u8>> {
+    let content = fs::read(ca_certificate_path()).ok()?;
+    let (_, pem) = x509_parser::pem::parse_x509_pem(&content).ok()?;
+    let certificate = pem.parse_x509().ok()?;
+    let identifier = certificate.iter_extensions().find_map(|extension| match extension.parsed_extension() {
+        ParsedExtension::SubjectKeyIdentifier(identifier) => Some(identifier.0.to_vec()),
+        _ => None,
+    });
+    identifier
+}
+
+/// Valid from yesterday, so clocks which are a bit off don't matter, for the given number of days
+fn set_validity(params: &mut CertificateParams, days: u64) {
+    let yesterday = (Utc::now() - chrono::Duration::days(1)).date_naive();
+    params.not_before = date_time_ymd(yesterday.year(), yesterday.month() as u8, yesterday.day() as u8);
+    params.not_after = params.not_before + Duration::from_secs(days * 24 * 60 * 60);
+}
+
+/// Writes a private key, only readable by the user
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn tls_config_for_traefik() {
+    let config: serde_yaml::Value = serde_yaml::from_str(&tls_config("shop")).unwrap();
+    assert_eq!(config["tls"]["certificates"][0]["certFile"].as_str(), Some("/certs/shop.pem"));
+    assert_eq!(config["tls"]["certificates"][0]["keyFile"].as_str(), Some("/certs/shop.key.pem"));
+
+    let config: serde_yaml::Value = serde_yaml::from_str(&tls_config(DEFAULT_CERTIFICATE)).unwrap();
+    let default = &config["tls"]["stores"]["default"]["defaultCertificate"];
+    assert_eq!(default["certFile"].as_str(), Some("/certs/dev-cli-default.pem"));
+    assert_eq!(config["tls"]["options"]["tls-opts"]["minVersion"].as_str(), Some("VersionTLS12"));
+}
@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+
+use super::docker_compose::{Config, Service, ServiceBuild};
+
+/// Reads, interpolates and merges the given compose files the way `docker compose config` does.
+/// Later files override earlier ones, relative paths are resolved against the directory of the
+/// first file. `COMPOSE_FILE` is ignored, the files are always the ones given here.
+pub fn load(files: &[PathBuf]) -> Result<Config> {
+    load_with_environment(files, std::env::vars().collect())
+}
+
+/// Like `load`, with the given variables in place of the environment of the process
+pub fn load_with_environment(files: &[PathBuf], environment: BTreeMap<String, String>) -> Result<Config> {
+    let project_dir = match files.first().and_then(|file| file.parent()) {
+        Some(project_dir) => project_dir,
+        None => return Err(anyhow!("No compose file given")),
+    };
+
+    let mut documents = vec![];
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .with_context(|| format!("Could not read {}", file.display()))?;
+        let document: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("Could not parse {}", file.display()))?;
+        documents.push((file, document));
+    }
+
+    let variables = variables(project_dir, &documents, environment)?;
+
+    let mut config = Config::default();
+    for (file, document) in documents {
+        let document = interpolate_value(document, &variables)
+            .with_context(|| format!("Could not interpolate {}", file.display()))?;
+        let file_config: Config = serde_yaml::from_value(document)
+            .with_context(|| format!("Invalid compose file {}", file.display()))?;
+        config = merge_config(config, file_config);
+    }
+
+    config.name = variables.get("COMPOSE_PROJECT_NAME").cloned();
+    for service in config.services.values_mut() {
+        resolve_paths(service, project_dir);
+    }
+
+    Ok(config)
+}
+
+/// Reads a `.env` file into a map
+pub fn read_env_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    parse_env(&content).with_context(|| format!("Invalid env file {}", path.display()))
+}
+
+/// Parses the content of a `.env` file. Supports comments, `export KEY=value` and quoted values.
+pub fn parse_env(content: &str) -> Result<BTreeMap<String, String>> {
+    let mut variables = BTreeMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => return Err(anyhow!("Invalid line: {}", line)),
+        };
+
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            value[1..value.len() - 1].replace("\\n", "\n").replace("\\\"", "\"")
+        } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+            value[1..value.len() - 1].to_string()
+        } else {
+            match value.split_once(" #") {
+                Some((value, _comment)) => value.trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        variables.insert(key.to_string(), value);
+    }
+
+    Ok(variables)
+}
+
+/// Replaces `$VAR`, `${VAR}`, `${VAR:-default}`, `${VAR-default}`, `${VAR:?error}`,
+/// `${VAR?error}`, `${VAR:+replacement}` and `${VAR+replacement}`. `$$` is a literal `$`.
+/// Unset variables are replaced by an empty string.
+pub fn interpolate(input: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((_, char)) = chars.next() {
+        if char != '$' {
+            output.push(char);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some((_, '$')) => {
+                chars.next();
+                output.push('$');
+            }
+            Some((start, '{')) => {
+                chars.next();
+                let mut depth = 1;
+                let mut end = None;
+                for (index, char) in chars.by_ref() {
+                    match char {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        end = Some(index);
+                        break;
+                    }
+                }
+                let end = end.ok_or_else(|| anyhow!("Missing closing brace in '{}'", input))?;
+                output.push_str(&resolve_expression(&input[start + 1..end], variables)?);
+            }
+            Some((start, char)) if char.is_ascii_alphabetic() || char == '_' => {
+                let mut end = input.len();
+                while let Some(&(index, char)) = chars.peek() {
+                    if !(char.is_ascii_alphanumeric() || char == '_') {
+                        end = index;
+                        break;
+                    }
+                    chars.next();
+                }
+                if let Some(value) = variables.get(&input[start..end]) {
+                    output.push_str(value);
+                }
+            }
+            _ => output.push('$'),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Resolves the content of `${...}`
+fn resolve_expression(expression: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let name_end = expression
+        .find(|char: char| !(char.is_ascii_alphanumeric() || char == '_'))
+        .unwrap_or(expression.len());
+    let (name, modifier) = expression.split_at(name_end);
+    if name.is_empty() {
+        return Err(anyhow!("Invalid interpolation format for '${{{}}}'", expression));
+    }
+
+    let value = variables.get(name).map(String::as_str);
+    let is_set = value.is_some();
+    let is_set_and_not_empty = value.map(|value| !value.is_empty()).unwrap_or(false);
+
+    let (operator, argument) = match modifier.char_indices().nth(1) {
+        Some((_, '-' | '?' | '+')) if modifier.starts_with(':') => modifier.split_at(2),
+        _ => modifier.split_at(modifier.len().min(1)),
+    };
+
+    match operator {
+        "" => Ok(value.unwrap_or_default().to_string()),
+        ":-" if is_set_and_not_empty => Ok(value.unwrap_or_default().to_string()),
+        "-" if is_set => Ok(value.unwrap_or_default().to_string()),
+        ":-" | "-" => interpolate(argument, variables),
+        ":?" if is_set_and_not_empty => Ok(value.unwrap_or_default().to_string()),
+        "?" if is_set => Ok(value.unwrap_or_default().to_string()),
+        ":?" | "?" => Err(anyhow!("Required variable {} is missing a value: {}", name, argument)),
+        ":+" if is_set_and_not_empty => interpolate(argument, variables),
+        "+" if is_set => interpolate(argument, variables),
+        ":+" | "+" => Ok(String::new()),
+        _ => Err(anyhow!("Invalid interpolation format for '${{{}}}'", expression)),
+    }
+}
+
+/// Variables available for interpolation. The environment overrides `.env`, and
+/// `COMPOSE_PROJECT_NAME` falls back to the `name` of the project or its directory.
+fn variables(
+    project_dir: &Path,
+    documents: &[(&PathBuf, serde_yaml::Value)],
+    environment: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>> {
+    let mut variables = BTreeMap::new();
+    let dot_env = project_dir.join(".env");
+    if dot_env.is_file() {
+        variables.extend(read_env_file(&dot_env)?);
+    }
+    variables.extend(environment);
+
+    if !variables.contains_key("COMPOSE_PROJECT_NAME") {
+        let name = documents
+            .iter()
+            .rev()
+            .find_map(|(_, document)| document.get("name").and_then(|name| name.as_str()))
+            .map(|name| interpolate(name, &variables))
+            .transpose()?;
+        let name = match name {
+            Some(name) => name,
+            None => project_name_from_dir(project_dir),
+        };
+        variables.insert(String::from("COMPOSE_PROJECT_NAME"), name);
+    }
+
+    Ok(variables)
+}
+
+/// The project name docker compose derives from a directory: lowercase, only `a-z`, `0-9`, `-` and `_`
+pub fn project_name_from_dir(project_dir: &Path) -> String {
+    let dir_name = project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf())
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    dir_name
+        .to_lowercase()
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric() || *char == '-' || *char == '_')
+        .collect::<String>()
+        .trim_start_matches(['-', '_'])
+        .to_string()
+}
+
+/// Interpolates all string values (not keys) of a document
+fn interpolate_value(value: serde_yaml::Value, variables: &BTreeMap<String, String>) -> Result<serde_yaml::Value> {
+    Ok(match value {
+        serde_yaml::Value::String(string) => serde_yaml::Value::String(interpolate(&string, variables)?),
+        serde_yaml::Value::Sequence(sequence) => serde_yaml::Value::Sequence(
+            sequence
+                .into_iter()
+                .map(|value| interpolate_value(value, variables))
+                .collect::<Result<_>>()?,
+        ),
+        serde_yaml::Value::Mapping(mapping) => serde_yaml::Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| Ok((key, interpolate_value(value, variables)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => value,
+    })
+}
+
+fn merge_config(mut base: Config, other: Config) -> Config {
+    base.name = other.name.or(base.name);
+    for (name, service) in other.services {
+        let merged = match base.services.remove(&name) {
+            Some(base_service) => merge_service(base_service, service),
+            None => service,
+        };
+        base.services.insert(name, merged);
+    }
+    base.networks.extend(other.networks);
+    base.volumes.extend(other.volumes);
+    base.secrets.extend(other.secrets);
+    base
+}
+
+/// Merges two definitions of a service following the compose specification: scalars and
+/// `command`/`entrypoint` are replaced, mappings are merged, ports are appended, and volumes and
+/// secrets are replaced per target.
+fn merge_service(mut base: Service, other: Service) -> Service {
+    base.build = match (base.build, other.build) {
+        (Some(base_build), Some(other_build)) => Some(merge_build(base_build, other_build)),
+        (base_build, other_build) => other_build.or(base_build),
+    };
+    base.command = other.command.or(base.command);
+    base.container_name = other.container_name.or(base.container_name);
+    base.depends_on.extend(other.depends_on);
+    base.entrypoint = other.entrypoint.or(base.entrypoint);
+    base.environment.extend(other.environment);
+    base.image = other.image.or(base.image);
+    base.init = other.init.or(base.init);
+    base.labels.extend(other.labels);
+    base.networks.extend(other.networks);
+    for port in other.ports {
+        if !base.ports.contains(&port) {
+            base.ports.push(port);
+        }
+    }
+    base.restart = other.restart.or(base.restart);
+    for secret in other.secrets {
+        let target = secret.target.as_ref().unwrap_or(&secret.source);
+        base.secrets.retain(|base_secret| base_secret.target.as_ref().unwrap_or(&base_secret.source) != target);
+        base.secrets.push(secret);
+    }
+    base.user = other.user.or(base.user);
+    for volume in other.volumes {
+        base.volumes.retain(|base_volume| base_volume.target != volume.target);
+        base.volumes.push(volume);
+    }
+    base.working_dir = other.working_dir.or(base.working_dir);
+    base
+}
+
+fn merge_build(mut base: ServiceBuild, other: ServiceBuild) -> ServiceBuild {
+    base.context = other.context;
+    base.dockerfile = other.dockerfile.or(base.dockerfile);
+    base.args.extend(other.args);
+    base.target = other.target.or(base.target);
+    base
+}
+
+/// Makes bind mount sources and build contexts absolute, as `docker compose config` does
+fn resolve_paths(service: &mut Service, project_dir: &Path) {
+    for volume in service.volumes.iter_mut().filter(|volume| volume.volume_type == "bind") {
+        if let Some(source) = &volume.source {
+            volume.source = Some(resolve_path(project_dir, source));
+        }
+    }
+    if let Some(build) = &mut service.build {
+        // Remote contexts (git repositories, tarballs) are left untouched
+        if !build.context.contains("://") && !build.context.starts_with("git@") {
+            build.context = resolve_path(project_dir, &build.context);
+        }
+    }
+}
+
+fn resolve_path(project_dir: &Path, path: &str) -> String {
+    let path = match path.strip_prefix('~') {
+        Some(rest) => dirs::home_dir()
+            .unwrap_or_default()
+            .join(rest.trim_start_matches('/')),
+        None => project_dir.join(path),
+    };
+
+    // Lexically normalize `.` and `..`, the path might not exist (yet)
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved.to_string_lossy().to_string()
+}
+
+#[test]
+fn load_with_override_and_interpolation() -> Result<(), Box<dyn std::error::Error>> {
+    use assert_fs::prelude::*;
+
+    let project_dir = assert_fs::TempDir::new()?;
+    project_dir.child(".env").write_str("# Comment\nTLD=test\nexport DB_IMAGE='mariadb:11'\n")?;
+    project_dir.child("compose.yml").write_str(
+        "name: example\n\
+         services:\n  \
+           web:\n    \
+             image: nginx\n    \
+             ports: ['${WEB_PORT:-8080}:80']\n    \
+             volumes: ['./src:/var/www/html', 'cache:/var/cache']\n    \
+             labels:\n      \
+               - traefik.http.routers.${COMPOSE_PROJECT_NAME}.rule=Host(`${COMPOSE_PROJECT_NAME}.${TLD}`)\n      \
+               - price=$$5\n  \
+           db:\n    \
+             image: ${DB_IMAGE}\n",
+    )?;
+    project_dir.child("compose.override.yml").write_str(
+        "services:\n  \
+           web:\n    \
+             volumes: ['../shared:/var/cache']\n    \
+             labels: {debug: 'true'}\n",
+    )?;
+
+    // An empty environment, so variables like `TLD` of the shell running the test don't interfere
+    let config = load_with_environment(&[
+        project_dir.path().join("compose.yml"),
+        project_dir.path().join("compose.override.yml"),
+    ], BTreeMap::new())?;
+    let web = &config.services["web"];
+
+    assert_eq!(config.name.as_deref(), Some("example"));
+    assert_eq!(web.labels["traefik.http.routers.example.rule"], "Host(`example.test`)");
+    assert_eq!(web.labels["price"], "$5");
+    assert_eq!(web.labels["debug"], "true");
+    assert_eq!(web.ports[0].published.as_deref(), Some("8080"));
+    assert_eq!(web.volumes.len(), 2);
+    assert_eq!(web.volumes[0].source, Some(resolve_path(project_dir.path(), "src")));
+    assert_eq!(web.volumes[1].target, "/var/cache");
+    assert_eq!(web.volumes[1].volume_type, "bind");
+    assert_eq!(config.services["db"].image.as_deref(), Some("mariadb:11"));
+
+    let variables = BTreeMap::from([(String::from("SET"), String::from("value"))]);
+    assert_eq!(interpolate("${UNSET:-${SET}}", &variables)?, "value");
+    assert_eq!(interpolate("${SET:+replaced}", &variables)?, "replaced");
+    assert!(interpolate("${UNSET:?must be set}", &variables).is_err());
+
+    Ok(())
+}
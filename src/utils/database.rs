@@ -0,0 +1,277 @@
+use std::io::{BufRead, Read, Write};
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::docker_compose::Config;
+
+const MYSQL_CREDENTIALS: &str = "--user=root --password=\"${MYSQL_ROOT_PASSWORD:-$MARIADB_ROOT_PASSWORD}\"";
+const POSTGRES_CREDENTIALS: &str = "--username=\"${POSTGRES_USER:-postgres}\"";
+
+/// The database servers dev-cli knows how to dump and import
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    MySql,
+    Postgres,
+}
+
+impl Engine {
+    /// Detects the engine from the image of the database service
+    pub fn detect(config: &Config, service: &str) -> Result<Self> {
+        let service_config = config.services
+            .get(service)
+            .ok_or_else(|| anyhow!("The database service '{}' does not exist, check `database_container` in the config", service))?;
+        let image = service_config.image
+            .as_deref()
+            .ok_or_else(|| anyhow!("The database service '{}' has no image to detect the database from", service))?;
+
+        Self::from_image(image)
+            .ok_or_else(|| anyhow!("Could not detect the database of '{}' from the image {}", service, image))
+    }
+
+    pub fn from_image(image: &str) -> Option<Self> {
+        let name = image_name(image);
+
+        if ["mysql", "mariadb", "percona"].iter().any(|engine| name.contains(engine)) {
+            Some(Engine::MySql)
+        } else if ["postgres", "postgis", "timescaledb"].iter().any(|engine| name.contains(engine)) {
+            Some(Engine::Postgres)
+        } else {
+            None
+        }
+    }
+
+    /// The server family of an image. MySQL and MariaDB share an engine, but their dumps are
+    /// not interchangeable, so restores compare this.
+    pub fn flavour(image: &str) -> Option<&'static str> {
+        let name = image_name(image);
+        if name.contains("mariadb") {
+            Some("mariadb")
+        } else if ["mysql", "percona"].iter().any(|flavour| name.contains(flavour)) {
+            Some("mysql")
+        } else if ["postgres", "postgis", "timescaledb"].iter().any(|flavour| name.contains(flavour)) {
+            Some("postgres")
+        } else {
+            None
+        }
+    }
+
+    /// The database the official images create from their environment variables
+    pub fn default_database(&self, config: &Config, service: &str) -> Option<String> {
+        let variables: &[&str] = match self {
+            Engine::MySql => &["MYSQL_DATABASE", "MARIADB_DATABASE"],
+            Engine::Postgres => &["POSTGRES_DB", "POSTGRES_USER"],
+        };
+        let environment = &config.services.get(service)?.environment;
+        variables
+            .iter()
+            .find_map(|variable| environment.get(*variable).cloned().flatten())
+    }
+
+    /// A shell script for `sh -c` in the container which writes the dump to stdout
+    ///
+    /// The credentials come from the environment of the container, so they don't have to be known here.
+    pub fn dump_script(&self, database: Option<&str>) -> String {
+        match self {
+            Engine::MySql => format!(
+                "{} if command -v mariadb-dump >/dev/null; then dump=mariadb-dump; else dump=mysqldump; fi; \
+                exec $dump --single-transaction --routines --triggers {} \"$db\"",
+                self.database_variable(database),
+                MYSQL_CREDENTIALS,
+            ),
+            Engine::Postgres => format!(
+                "{} exec pg_dump --clean --if-exists --no-owner {} \"$db\"",
+                self.database_variable(database),
+                POSTGRES_CREDENTIALS,
+            ),
+        }
+    }
+
+    /// A shell script for `sh -c` in the container which imports a dump from stdin
+    pub fn import_script(&self, database: Option<&str>, recreate: bool) -> String {
+        match self {
+            Engine::MySql => {
+                let mut script = format!(
+                    "{} if command -v mariadb >/dev/null; then client=mariadb; else client=mysql; fi;",
+                    self.database_variable(database)
+                );
+                if recreate {
+                    script += &format!(
+                        " $client {} -e \"DROP DATABASE IF EXISTS \\`$db\\`; CREATE DATABASE \\`$db\\`\" || exit;",
+                        MYSQL_CREDENTIALS
+                    );
+                }
+                script + &format!(" exec $client {} \"$db\"", MYSQL_CREDENTIALS)
+            }
+            Engine::Postgres => {
+                let mut script = self.database_variable(database);
+                if recreate {
+                    script += &format!(
+                        " psql --quiet {} --dbname=postgres -c \"DROP DATABASE IF EXISTS \\\"$db\\\" WITH (FORCE)\" -c \"CREATE DATABASE \\\"$db\\\"\" || exit;",
+                        POSTGRES_CREDENTIALS
+                    );
+                }
+                script + &format!(" exec psql --quiet --set ON_ERROR_STOP=1 {} --dbname=\"$db\"", POSTGRES_CREDENTIALS)
+            }
+        }
+    }
+
+    /// A shell script for `sh -c` in the container which prints the version of the server
+    pub fn version_script(&self) -> &'static str {
+        match self {
+            Engine::MySql => "if command -v mariadbd >/dev/null; then mariadbd --version; else mysqld --version; fi",
+            Engine::Postgres => "postgres --version",
+        }
+    }
+
+    /// Sets `$db` to the given database, or to the one the container created
+    fn database_variable(&self, database: Option<&str>) -> String {
+        let database = match (database, self) {
+            (Some(database), _) => shell_quote(database),
+            (None, Engine::MySql) => String::from("\"${MYSQL_DATABASE:-$MARIADB_DATABASE}\""),
+            (None, Engine::Postgres) => String::from("\"${POSTGRES_DB:-${POSTGRES_USER:-postgres}}\""),
+        };
+        format!("db={};", database)
+    }
+}
+
+impl std::fmt::Display for Engine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Engine::MySql => write!(f, "mysql"),
+            Engine::Postgres => write!(f, "postgres"),
+        }
+    }
+}
+
+/// Extracts the version from the output of `Engine::version_script`, e.g. `11.2.2` from
+/// `mariadbd  Ver 11.2.2-MariaDB-1:11.2.2+maria~ubu2204 for debian-linux-gnu`
+/// Only the name of an image counts, not the registry or the tag
+fn image_name(image: &str) -> &str {
+    let name = image.rsplit('/').next().unwrap_or(image);
+    name.split([':', '@']).next().unwrap_or(name)
+}
+
+pub fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .filter(|word| word.starts_with(|char: char| char.is_ascii_digit()) && word.contains('.'))
+        .map(|word| {
+            let end = word.find(|char: char| !char.is_ascii_digit() && char != '.').unwrap_or(word.len());
+            word[..end].trim_end_matches('.').to_string()
+        })
+        .next()
+}
+
+/// The major and minor version, dumps of a newer server often can't be read by an older one
+pub fn major_minor(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|minor| minor.parse().ok()).unwrap_or(0);
+    Some((major, minor))
+}
+
+/// How a dump file is compressed
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Detects the compression from the magic bytes at the start of `reader`, without consuming them
+    pub fn sniff(reader: &mut impl BufRead) -> std::io::Result<Self> {
+        let start = reader.fill_buf()?;
+        Ok(if start.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        })
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "sql",
+            Compression::Gzip => "sql.gz",
+            Compression::Zstd => "sql.zst",
+        }
+    }
+
+    /// Copies `reader` into `writer` while compressing, returns the number of uncompressed bytes
+    pub fn compress(&self, reader: &mut impl Read, writer: impl Write) -> std::io::Result<u64> {
+        match self {
+            Compression::None => {
+                let mut writer = writer;
+                let bytes = std::io::copy(reader, &mut writer)?;
+                writer.flush()?;
+                Ok(bytes)
+            }
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+                let bytes = std::io::copy(reader, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(bytes)
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(writer, 0)?;
+                let bytes = std::io::copy(reader, &mut encoder)?;
+                encoder.finish()?.flush()?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Wraps `reader` so it returns the uncompressed data
+    pub fn decompress<'a>(&self, reader: impl BufRead + Send + 'a) -> std::io::Result<Box<dyn Read + Send + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+        })
+    }
+}
+
+/// Wraps a value in single quotes for `sh`
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[test]
+fn detect_engine_and_compression() {
+    assert_eq!(Engine::from_image("mariadb:11"), Some(Engine::MySql));
+    assert_eq!(Engine::from_image("docker.io/library/mysql:8.0"), Some(Engine::MySql));
+    assert_eq!(Engine::from_image("postgis/postgis:16-3.4"), Some(Engine::Postgres));
+    assert_eq!(Engine::from_image("postgres@sha256:abc"), Some(Engine::Postgres));
+    assert_eq!(Engine::from_image("redis:7"), None);
+    // The registry must not be mistaken for the image
+    assert_eq!(Engine::from_image("mysql.example.com/redis"), None);
+
+    assert_eq!(Compression::from_path(Path::new("dumps/app.sql.gz")), Compression::Gzip);
+    assert_eq!(Compression::from_path(Path::new("app.sql.zst")), Compression::Zstd);
+    assert_eq!(Compression::from_path(Path::new("app.sql")), Compression::None);
+
+    assert_eq!(parse_version("mariadbd  Ver 11.2.2-MariaDB-1:11.2.2+maria~ubu2204 for debian-linux-gnu").as_deref(), Some("11.2.2"));
+    assert_eq!(parse_version("postgres (PostgreSQL) 16.1 (Debian 16.1-1.pgdg120+1)").as_deref(), Some("16.1"));
+    assert_eq!(major_minor("8.0.35"), Some((8, 0)));
+
+    let mut compressed = vec![];
+    Compression::Zstd.compress(&mut "SELECT 1;".as_bytes(), &mut compressed).unwrap();
+    let mut reader = compressed.as_slice();
+    assert_eq!(Compression::sniff(&mut reader).unwrap(), Compression::Zstd);
+    let mut decompressed = String::new();
+    Compression::Zstd.decompress(reader).unwrap().read_to_string(&mut decompressed).unwrap();
+    assert_eq!(decompressed, "SELECT 1;");
+}
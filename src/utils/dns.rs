@@ -0,0 +1,188 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use bollard::container::InspectContainerOptions;
+use bollard::Docker;
+
+use crate::DATA_DIR;
+
+/// The container name of dnsmasq in `files/docker/compose.yml`
+pub const DNS_CONTAINER: &str = "dns";
+
+/// Where the hostnames are written to if the DNS container is disabled
+#[cfg(not(windows))]
+pub const HOSTS_FILE: &str = "/etc/hosts";
+#[cfg(windows)]
+pub const HOSTS_FILE: &str = r"C:\Windows\System32\drivers\etc\hosts";
+
+/// The dnsmasq snippet of a project, mounted into the DNS container as `/etc/dnsmasq.d/<project>.conf`
+pub fn snippet_path(project: &str) -> PathBuf {
+    DATA_DIR.join("dns").join(format!("{}.conf", project))
+}
+
+/// The hostnames in the dnsmasq snippet of a project
+pub fn read_snippet(project: &str) -> Vec<String> {
+    fs::read_to_string(snippet_path(project))
+        .map(|content| parse_snippet(&content))
+        .unwrap_or_default()
+}
+
+/// Writes the dnsmasq snippet of a project, or removes it if there are no hostnames left
+pub fn write_snippet(project: &str, hosts: &[String]) -> Result<()> {
+    let path = snippet_path(project);
+    if hosts.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        };
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, render_snippet(project, hosts))?;
+    Ok(())
+}
+
+fn parse_snippet(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("address=/"))
+        .filter_map(|rest| rest.split('/').next())
+        .filter(|host| !host.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn render_snippet(project: &str, hosts: &[String]) -> String {
+    let mut content = format!("# Generated by dev-cli for the project {}\n", project);
+    for host in hosts {
+        content += &format!("address=/{}/127.0.0.1\n", host);
+    }
+    content
+}
+
+/// dnsmasq only reads its config files on startup, so the container is restarted. Returns false
+/// if the container doesn't run, it reads the snippets once the proxy is started.
+pub async fn reload(docker: &Docker) -> Result<bool> {
+    let running = match docker.inspect_container(DNS_CONTAINER, None::<InspectContainerOptions>).await {
+        Ok(container) => container.state.and_then(|state| state.running).unwrap_or(false),
+        Err(_) => false,
+    };
+    if !running {
+        return Ok(false);
+    }
+
+    docker.restart_container(DNS_CONTAINER, None).await?;
+    Ok(true)
+}
+
+/// The hostnames in the block of a project in a hosts file
+pub fn hosts_block(content: &str, project: &str) -> Vec<String> {
+    let (begin, end) = block_markers(project);
+    content
+        .lines()
+        .skip_while(|line| line.trim() != begin)
+        .skip(1)
+        .take_while(|line| line.trim() != end)
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split_whitespace().skip(1).map(String::from).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Moves the block of a project to the end of a hosts file with the given hostnames, or removes it
+/// if there are none. Everything outside the block is left as it is.
+pub fn set_hosts_block(content: &str, project: &str, hosts: &[String]) -> String {
+    let (begin, end) = block_markers(project);
+    let mut lines: Vec<&str> = vec![];
+    let mut in_block = false;
+    for line in content.lines() {
+        if line.trim() == begin {
+            // The blank line separating the block goes with it
+            if lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+            in_block = true;
+        } else if in_block && line.trim() == end {
+            in_block = false;
+        } else if !in_block {
+            lines.push(line);
+        }
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    if !hosts.is_empty() {
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content += &format!("{}\n", begin);
+        for host in hosts {
+            content += &format!("127.0.0.1 {}\n", host);
+        }
+        content += &format!("{}\n", end);
+    }
+    content
+}
+
+/// Removes the block of a project from a hosts file, returns whether there was one
+pub fn remove_hosts_block(path: &Path, project: &str) -> Result<bool> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let (begin, _) = block_markers(project);
+    if !content.lines().any(|line| line.trim() == begin) {
+        return Ok(false);
+    }
+    write_hosts_file(path, &set_hosts_block(&content, project, &[]))?;
+    Ok(true)
+}
+
+fn block_markers(project: &str) -> (String, String) {
+    (format!("# BEGIN dev-cli {}", project), format!("# END dev-cli {}", project))
+}
+
+/// Writes the hosts file, through `sudo tee` if the user may not write it
+pub fn write_hosts_file(path: &Path, content: &str) -> Result<()> {
+    match fs::write(path, content) {
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => {
+            println!("Writing {} requires root privileges, asking sudo ...", path.display());
+            let status = subprocess::Exec::cmd("sudo")
+                .arg("tee")
+                .arg(path)
+                .stdin(content)
+                .stdout(subprocess::NullFile)
+                .join()?;
+            if !status.success() {
+                return Err(anyhow!("Could not write {}", path.display()));
+            }
+            Ok(())
+        }
+        result => Ok(result?),
+    }
+}
+
+#[test]
+fn manage_hostnames() {
+    let hosts = vec![String::from("shop.test"), String::from("api.shop.localhost")];
+    assert_eq!(parse_snippet(&render_snippet("shop", &hosts)), hosts);
+
+    let content = "127.0.0.1 localhost\n::1 localhost\n";
+    let content = set_hosts_block(content, "shop", &hosts);
+    let content = set_hosts_block(&content, "blog", &[String::from("blog.test")]);
+    assert_eq!(
+        content,
+        "127.0.0.1 localhost\n::1 localhost\n\n\
+         # BEGIN dev-cli shop\n127.0.0.1 shop.test\n127.0.0.1 api.shop.localhost\n# END dev-cli shop\n\n\
+         # BEGIN dev-cli blog\n127.0.0.1 blog.test\n# END dev-cli blog\n"
+    );
+    assert_eq!(hosts_block(&content, "shop"), hosts);
+
+    let content = set_hosts_block(&content, "shop", &[]);
+    assert_eq!(content, "127.0.0.1 localhost\n::1 localhost\n\n# BEGIN dev-cli blog\n127.0.0.1 blog.test\n# END dev-cli blog\n");
+    assert!(hosts_block(&content, "shop").is_empty());
+}
@@ -1,74 +1,147 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde::de::{MapAccess, Visitor};
+use anyhow::{anyhow, Context};
+
+use super::{addons, compose_loader};
+
 #[derive(Debug)]
 pub struct DockerCompose {
     file: std::path::PathBuf,
+    config_from_docker: bool,
 }
 
 impl DockerCompose {
     pub fn new(file: std::path::PathBuf) -> Self {
         Self {
             file,
+            config_from_docker: false,
+        }
+    }
+
+    /// Read the config with `docker compose config` instead of parsing the files natively
+    pub fn with_config_from_docker(mut self, config_from_docker: bool) -> Self {
+        self.config_from_docker = config_from_docker;
+        self
+    }
+
+    /// The compose files of the project, `compose.override.yml` is picked up like docker compose does
+    /// and the generated file of the enabled add-ons comes last
+    pub fn files(&self) -> Vec<std::path::PathBuf> {
+        let mut files = vec![self.file.clone()];
+        let override_file = self.file.with_file_name("compose.override.yml");
+        if override_file.is_file() {
+            files.push(override_file);
+        }
+        if let Some(project_root) = self.file.parent() {
+            let addons_file = addons::override_file(project_root);
+            if addons_file.is_file() {
+                files.push(addons_file);
+            }
+        }
+        files
+    }
+
+    /// `docker compose` with all files of the project. Passing one file with `-f` turns off the
+    /// lookup of `compose.override.yml`, so every file is passed. This also means `COMPOSE_FILE`
+    /// is ignored, like the native parser does.
+    fn command(&self) -> subprocess::Exec {
+        let mut cmd = subprocess::Exec::cmd("docker").arg("compose");
+        for file in self.files() {
+            cmd = cmd.arg("-f").arg(file);
         }
+        cmd.cwd(self.file.parent().unwrap())
     }
 
-    pub fn config(&self) -> Result<Config, serde_yaml::Error> {
-        let output_cmd = if cfg!(target_os = "windows") {
+    pub fn config(&self) -> anyhow::Result<Config> {
+        if self.config_from_docker {
+            return self.config_from_docker();
+        }
+        compose_loader::load(&self.files())
+    }
+
+    /// Lets docker compose resolve the config, useful to cross-check the native parser
+    pub fn config_from_docker(&self) -> anyhow::Result<Config> {
+        if cfg!(target_os = "windows") {
             panic!("Windows is not supported yet")
             //std::process::Command::new("cmd")
             //    .args(["/C", "echo hello"])
             //    .output()
-        } else {
-            std::process::Command::new("sh")
-                .arg("-c")
-                .arg("docker compose config")
-                .current_dir(&self.file.parent().unwrap())
-                .output()
-        };
-        let output = match output_cmd {
-            Ok(output) => output,
-            Err(error) => {
-                println!("Docker doesn't seem to be turned on ({})", error);
-                sysexits::ExitCode::OsErr.exit()
-            },
-        };
-        let config_string = match std::str::from_utf8(&output.stdout) {
-            Ok(stdout) => stdout,
-            Err(error) => {
-                println!("Error: {}", error);
-                sysexits::ExitCode::OsErr.exit()
-            },
-        };
-        let config = serde_yaml::from_str::<Config>(config_string);
-        config
+        }
+
+        let mut command = std::process::Command::new("docker");
+        command.arg("compose");
+        for file in self.files() {
+            command.arg("-f").arg(file);
+        }
+        let output = command
+            .arg("config")
+            .current_dir(self.file.parent().unwrap())
+            .output()
+            .context("Could not run docker compose")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "docker compose config failed ({}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let config_string = std::str::from_utf8(&output.stdout)?;
+        Ok(serde_yaml::from_str::<Config>(config_string)?)
     }
 
-    pub fn exec(&self, service: Option<String>, user: Option<String>, command: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-        let service_to_exec = match service {
-            Some(service) => service,
-            None => {
-                let config = self.config().unwrap();
-                let first_service = config.services.keys().next().unwrap();
-                first_service.to_string()
-            },
-        };
+    /// Returns the given service, or the first service of the project if none was given
+    pub fn service_or_default(&self, service: Option<String>) -> anyhow::Result<String> {
+        match service {
+            Some(service) => Ok(service),
+            None => self.config()
+                .context("Could not read the compose config to find the default service")?
+                .services
+                .keys()
+                .next()
+                .cloned()
+                .ok_or_else(|| anyhow!("The project has no services, pass one with --service")),
+        }
+    }
 
+    /// Prepares a `docker compose exec` without running it, so the caller can decide how to handle the output
+    pub fn exec_cmd(&self, service: String, user: Option<String>, workdir: Option<String>, command: Vec<String>, tty: bool) -> subprocess::Exec {
         if cfg!(target_os = "windows") {
             panic!("Windows is not supported yet")
             //std::process::Command::new("cmd")
             //    .args(["/C", "echo hello"])
             //    .output()
-        } else {
-            let mut cmd = subprocess::Exec::cmd("docker").arg("compose").arg("exec");
-            cmd = match user {
-                Some(user) => cmd
-                    .arg("--user").arg(user),
-                None => cmd,
-            };
-            cmd
-                .arg(service_to_exec)
-                .args(&command)
-                .cwd(&self.file.parent().unwrap())
-                .join()?
+        }
+
+        let mut cmd = self.command().arg("exec");
+        if !tty {
+            cmd = cmd.arg("--no-TTY");
+        }
+        cmd = match user {
+            Some(user) => cmd
+                .arg("--user").arg(user),
+            None => cmd,
+        };
+        cmd = match workdir {
+            Some(workdir) => cmd
+                .arg("--workdir").arg(workdir),
+            None => cmd,
         };
+        cmd
+            .arg(service)
+            .args(&command)
+    }
+
+    pub fn exec(&self, service: Option<String>, user: Option<String>, command: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+        let service = self.service_or_default(service)?;
+        let status = self.exec_cmd(service, user, None, command, true).join()?;
+
+        if !status.success() {
+            return Err(format!("Command failed ({:?})", status).into());
+        }
         Ok(())
     }
 
@@ -85,10 +158,30 @@ impl DockerCompose {
             }
         }
 
-        let args = vec![vec!["compose", "up"], extra_args].concat();
-        let cmd = subprocess::Exec::cmd("docker")
-            .args(&args)
-            .cwd(&self.file.parent().unwrap())
+        let cmd = self.command()
+            .arg("up")
+            .args(&extra_args)
+            .join()?;
+
+        if !cmd.success() {
+            println!("Error: {:?}", cmd);
+            sysexits::ExitCode::OsErr.exit()
+        }
+        Ok(())
+    }
+
+    pub fn build(&self, services: Option<Vec<&str>>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut extra_args = vec![];
+
+        if let Some(services) = services {
+            for service in services {
+                extra_args.push(service);
+            }
+        }
+
+        let cmd = self.command()
+            .arg("build")
+            .args(&extra_args)
             .join()?;
 
         if !cmd.success() {
@@ -111,10 +204,9 @@ impl DockerCompose {
             }
         }
 
-        let args = vec![vec!["compose", "down"], extra_args].concat();
-        let cmd = subprocess::Exec::cmd("docker")
-            .args(&args)
-            .cwd(&self.file.parent().unwrap())
+        let cmd = self.command()
+            .arg("down")
+            .args(&extra_args)
             .join()?;
 
         if !cmd.success() {
@@ -123,94 +215,566 @@ impl DockerCompose {
         }
         Ok(())
     }
+
+    /// Like `down` with `--volumes`, but also removes the images built for the project and orphaned containers
+    pub fn remove(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let cmd = self.command()
+            .args(&["down", "--volumes", "--rmi", "local", "--remove-orphans"])
+            .join()?;
+
+        if !cmd.success() {
+            return Err(format!("Removing the project failed ({:?})", cmd).into());
+        }
+        Ok(())
+    }
 }
 
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
+/// The typed model of a compose file. Every field accepting both the short and the long syntax
+/// is normalized to the long syntax while deserializing, so `Config` looks the same whether it
+/// was read from a hand-written `compose.yml` or from the output of `docker compose config`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
-    name: String,
-    services: std::collections::BTreeMap<String, Service>,
-    networks: Option<std::collections::BTreeMap<String, Network>>,
-    volumes: Option<std::collections::BTreeMap<String, Volume>>,
-    secrets: Option<std::collections::BTreeMap<String, Secret>>,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Service {
-    // TODO: Check "https://serde.rs/string-or-struct.html" for how to handle "build"
-    //build: Option<String>,
-    //command: Option<String>,
-    container_name: Option<String>,
-    depends_on: Option<std::collections::BTreeMap<String, ServiceDependsOn>>,
-    environment: Option<std::collections::BTreeMap<String, String>>,
-    image: Option<String>,
-    init: Option<bool>,
-    labels: Option<std::collections::BTreeMap<String, String>>,
-    networks: Option<std::collections::BTreeMap<String, Option<String>>>,
-    ports: Option<Vec<ServicePorts>>,
-    secrets: Option<Vec<ServiceSecret>>,
-    volumes: Option<Vec<ServiceVolume>>,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceDependsOn {
-    condition: String,
-    required: bool
-}
-
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServicePorts {
-    mode: String,
-    target: u16,
-    published: String,
-    protocol: String,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceSecret {
-    source: String,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceVolume {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub services: BTreeMap<String, Service>,
+    #[serde(default, deserialize_with = "map_with_optional_values")]
+    pub networks: BTreeMap<String, Network>,
+    #[serde(default, deserialize_with = "map_with_optional_values")]
+    pub volumes: BTreeMap<String, Volume>,
+    #[serde(default, deserialize_with = "map_with_optional_values")]
+    pub secrets: BTreeMap<String, Secret>,
+}
+
+impl Config {
+    /// The services to pass to compose, `None` for all of them. Unknown services are refused,
+    /// as compose would only fail after stopping the others.
+    pub fn select_services<'a>(&self, services: &'a [String]) -> Result<Option<Vec<&'a str>>, String> {
+        if services.is_empty() {
+            return Ok(None);
+        }
+        if let Some(unknown) = services.iter().find(|service| !self.services.contains_key(*service)) {
+            return Err(format!("The project has no service '{}'", unknown));
+        }
+        Ok(Some(services.iter().map(String::as_str).collect()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Service {
+    #[serde(default, deserialize_with = "optional_string_or_struct")]
+    pub build: Option<ServiceBuild>,
+    pub command: Option<ServiceCommand>,
+    pub container_name: Option<String>,
+    #[serde(default, deserialize_with = "depends_on")]
+    pub depends_on: BTreeMap<String, ServiceDependsOn>,
+    pub entrypoint: Option<ServiceCommand>,
+    #[serde(default, deserialize_with = "list_or_map")]
+    pub environment: BTreeMap<String, Option<String>>,
+    pub image: Option<String>,
+    pub init: Option<bool>,
+    #[serde(default, deserialize_with = "labels")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "networks")]
+    pub networks: BTreeMap<String, ServiceNetwork>,
+    #[serde(default, deserialize_with = "ports")]
+    pub ports: Vec<ServicePort>,
+    pub restart: Option<String>,
+    #[serde(default, deserialize_with = "list_of_string_or_struct")]
+    pub secrets: Vec<ServiceSecret>,
+    pub user: Option<String>,
+    #[serde(default, deserialize_with = "list_of_string_or_struct")]
+    pub volumes: Vec<ServiceVolume>,
+    pub working_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceBuild {
+    #[serde(default = "default_build_context")]
+    pub context: String,
+    pub dockerfile: Option<String>,
+    #[serde(default, deserialize_with = "list_or_map")]
+    pub args: BTreeMap<String, Option<String>>,
+    pub target: Option<String>,
+}
+
+impl FromStr for ServiceBuild {
+    type Err = String;
+
+    fn from_str(context: &str) -> Result<Self, Self::Err> {
+        Ok(ServiceBuild {
+            context: context.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+/// `command` and `entrypoint` are either a single string, or a list of arguments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ServiceCommand {
+    String(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceDependsOn {
+    #[serde(default = "default_depends_on_condition")]
+    pub condition: String,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    pub restart: Option<bool>,
+}
+
+impl Default for ServiceDependsOn {
+    fn default() -> Self {
+        ServiceDependsOn {
+            condition: default_depends_on_condition(),
+            required: true,
+            restart: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceNetwork {
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub ipv4_address: Option<String>,
+    pub ipv6_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServicePort {
+    pub mode: Option<String>,
+    pub host_ip: Option<String>,
+    pub target: u16,
+    #[serde(default, deserialize_with = "optional_string_or_number")]
+    pub published: Option<String>,
+    pub protocol: Option<String>,
+}
+
+impl ServicePort {
+    /// Parses `[[host_ip:]published:]target[/protocol]`, where `published` and `target` may be
+    /// ranges (`8000-8001`), which results in one port per element of the range
+    fn parse_short_syntax(port: &str) -> Result<Vec<Self>, String> {
+        let (port_mapping, protocol) = match port.split_once('/') {
+            Some((port_mapping, protocol)) => (port_mapping, Some(protocol.to_string())),
+            None => (port, None),
+        };
+        let (host, target) = match port_mapping.rsplit_once(':') {
+            Some((host, target)) => (Some(host), target),
+            None => (None, port_mapping),
+        };
+        let (host_ip, published) = match host {
+            Some(host) => match host.rsplit_once(':') {
+                Some((host_ip, published)) => (
+                    Some(host_ip.trim_start_matches('[').trim_end_matches(']').to_string()),
+                    Some(published),
+                ),
+                None => (None, Some(host)),
+            },
+            None => (None, None),
+        };
+
+        let targets = parse_port_range(target)
+            .ok_or_else(|| format!("invalid container port in '{}'", port))?;
+        let published = match published.filter(|published| !published.is_empty()) {
+            Some(published) => Some(
+                parse_port_range(published)
+                    .ok_or_else(|| format!("invalid published port in '{}'", port))?
+            ),
+            None => None,
+        };
+
+        let mut ports = vec![];
+        for (index, target) in targets.clone().enumerate() {
+            let published = match &published {
+                // A single published port for a range of targets is passed through as is
+                Some(published) if published.len() == 1 => Some(published.start().to_string()),
+                // A published range for a single target lets Docker pick a free port of it
+                Some(published) if targets.len() == 1 => Some(format!("{}-{}", published.start(), published.end())),
+                Some(published) if published.len() == targets.len() => {
+                    Some((published.start() + index as u16).to_string())
+                }
+                Some(_) => return Err(format!("port ranges don't match in '{}'", port)),
+                None => None,
+            };
+            ports.push(ServicePort {
+                mode: Some(String::from("ingress")),
+                host_ip: host_ip.clone(),
+                target,
+                published,
+                protocol: Some(protocol.clone().unwrap_or_else(|| String::from("tcp"))),
+            });
+        }
+
+        Ok(ports)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceSecret {
+    pub source: String,
+    pub target: Option<String>,
+}
+
+impl FromStr for ServiceSecret {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Ok(ServiceSecret {
+            source: source.to_string(),
+            target: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolume {
+    /// One of `bind`, `volume` or `tmpfs`
     #[serde(rename = "type")]
-    volume_type: String,
-    source: String,
-    target: String,
-    bind: Option<ServiceVolumeBind>,
-    // TODO: Don't know the actual type of this
-    volume: Option<std::collections::BTreeMap<String, String>>,
+    pub volume_type: String,
+    /// Omitted for anonymous volumes
+    pub source: Option<String>,
+    pub target: String,
+    #[serde(default)]
+    pub read_only: bool,
+    pub bind: Option<ServiceVolumeBind>,
+    pub volume: Option<ServiceVolumeVolume>,
+}
+
+impl FromStr for ServiceVolume {
+    type Err = String;
+
+    /// Parses `[source:]target[:mode]`. Sources starting with `.`, `/` or `~` are host paths,
+    /// everything else is the name of a volume.
+    fn from_str(volume: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = volume.split(':').collect();
+        let (source, target, mode) = match parts.as_slice() {
+            [target] => (None, *target, None),
+            [source, target] => (Some(*source), *target, None),
+            [source, target, mode] => (Some(*source), *target, Some(*mode)),
+            _ => return Err(format!("invalid volume specification '{}'", volume)),
+        };
+        if target.is_empty() {
+            return Err(format!("invalid volume specification '{}'", volume));
+        }
+
+        let is_bind = source
+            .map(|source| source.starts_with(['.', '/', '~']))
+            .unwrap_or(false);
+        let read_only = mode
+            .map(|mode| mode.split(',').any(|option| option == "ro"))
+            .unwrap_or(false);
+
+        Ok(ServiceVolume {
+            volume_type: String::from(if is_bind { "bind" } else { "volume" }),
+            source: source.map(str::to_string),
+            target: target.to_string(),
+            read_only,
+            // The short syntax always creates missing host paths
+            bind: is_bind.then(|| ServiceVolumeBind {
+                create_host_path: Some(true),
+                ..Default::default()
+            }),
+            volume: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolumeBind {
+    pub create_host_path: Option<bool>,
+    pub propagation: Option<String>,
+    pub selinux: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceVolumeVolume {
+    pub nocopy: Option<bool>,
+    pub subpath: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Network {
+    pub name: Option<String>,
+    pub driver: Option<String>,
+    pub external: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Volume {
+    pub name: Option<String>,
+    pub driver: Option<String>,
+    pub external: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Secret {
+    pub name: Option<String>,
+    pub file: Option<String>,
+    pub environment: Option<String>,
+}
+
+fn default_depends_on_condition() -> String {
+    String::from("service_started")
+}
+
+fn default_build_context() -> String {
+    String::from(".")
+}
+
+fn default_true() -> bool {
+    true
 }
 
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct ServiceVolumeBind {
-    create_host_path: bool,
+fn parse_port_range(range: &str) -> Option<std::ops::RangeInclusive<u16>> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let port = range.parse().ok()?;
+            (port, port)
+        }
+    };
+    (start <= end).then_some(start..=end)
+}
+
+/// Converts the scalar value of a mapping (`KEY: 1`, `KEY: true`, ...) to a string
+fn scalar_to_string<E: de::Error>(value: serde_yaml::Value) -> Result<Option<String>, E> {
+    match value {
+        serde_yaml::Value::Null => Ok(None),
+        serde_yaml::Value::Bool(value) => Ok(Some(value.to_string())),
+        serde_yaml::Value::Number(value) => Ok(Some(value.to_string())),
+        serde_yaml::Value::String(value) => Ok(Some(value)),
+        _ => Err(E::custom("expected a string, number or boolean")),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListOrMap<V> {
+    List(Vec<String>),
+    Map(BTreeMap<String, V>),
 }
 
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Network {
-    name: String,
-    external: Option<bool>,
+/// Accepts both `["KEY=value", "KEY"]` and `{KEY: value, KEY: null}`
+fn list_or_map<'de, D>(deserializer: D) -> Result<BTreeMap<String, Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<ListOrMap<serde_yaml::Value>>::deserialize(deserializer)? {
+        Some(ListOrMap::List(list)) => Ok(list
+            .into_iter()
+            .map(|entry| match entry.split_once('=') {
+                Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                None => (entry, None),
+            })
+            .collect()),
+        Some(ListOrMap::Map(map)) => map
+            .into_iter()
+            .map(|(key, value)| Ok((key, scalar_to_string(value)?)))
+            .collect(),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+fn labels<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(list_or_map(deserializer)?
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect())
 }
 
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Volume {
-    name: String,
-    driver: Option<String>,
-    external: Option<bool>,
+fn depends_on<'de, D>(deserializer: D) -> Result<BTreeMap<String, ServiceDependsOn>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<ListOrMap<ServiceDependsOn>>::deserialize(deserializer)? {
+        Some(ListOrMap::List(list)) => list
+            .into_iter()
+            .map(|service| (service, ServiceDependsOn::default()))
+            .collect(),
+        Some(ListOrMap::Map(map)) => map,
+        None => BTreeMap::new(),
+    })
 }
 
-#[allow(dead_code)]
-#[derive(Debug, serde::Deserialize)]
-struct Secret {
-    name: String,
-    file: String,
+fn networks<'de, D>(deserializer: D) -> Result<BTreeMap<String, ServiceNetwork>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<ListOrMap<Option<ServiceNetwork>>>::deserialize(deserializer)? {
+        Some(ListOrMap::List(list)) => list
+            .into_iter()
+            .map(|network| (network, ServiceNetwork::default()))
+            .collect(),
+        Some(ListOrMap::Map(map)) => map
+            .into_iter()
+            .map(|(network, config)| (network, config.unwrap_or_default()))
+            .collect(),
+        None => BTreeMap::new(),
+    })
+}
+
+/// Ports can't use `string_or_struct`, as a single short entry with a range expands to several ports
+fn ports<'de, D>(deserializer: D) -> Result<Vec<ServicePort>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut ports = vec![];
+    for port in Option::<Vec<serde_yaml::Value>>::deserialize(deserializer)?.unwrap_or_default() {
+        match port {
+            serde_yaml::Value::Mapping(_) => {
+                ports.push(serde_yaml::from_value(port).map_err(de::Error::custom)?)
+            }
+            port => {
+                let port = scalar_to_string(port)?.unwrap_or_default();
+                ports.extend(ServicePort::parse_short_syntax(&port).map_err(de::Error::custom)?)
+            }
+        }
+    }
+    Ok(ports)
+}
+
+fn optional_string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    scalar_to_string(serde_yaml::Value::deserialize(deserializer)?)
+}
+
+/// Named entries without any configuration (`networks: {web: }`) are null
+fn map_with_optional_values<'de, D, V>(deserializer: D) -> Result<BTreeMap<String, V>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de> + Default,
+{
+    Ok(Option::<BTreeMap<String, Option<V>>>::deserialize(deserializer)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, value)| (key, value.unwrap_or_default()))
+        .collect())
+}
+
+/// Wraps an entry which can be written in the short (string) or the long (mapping) syntax
+/// https://serde.rs/string-or-struct.html
+struct StringOrStruct<T>(T);
+
+impl<'de, T> Deserialize<'de> for StringOrStruct<T>
+where
+    T: Deserialize<'de> + FromStr<Err = String>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StringOrStructVisitor<T>(PhantomData<fn() -> T>);
+
+        impl<'de, T> Visitor<'de> for StringOrStructVisitor<T>
+        where
+            T: Deserialize<'de> + FromStr<Err = String>,
+        {
+            type Value = T;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("string or map")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+                T::from_str(value).map_err(E::custom)
+            }
+
+            fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<T, M::Error> {
+                T::deserialize(de::value::MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer
+            .deserialize_any(StringOrStructVisitor(PhantomData))
+            .map(StringOrStruct)
+    }
+}
+
+fn optional_string_or_struct<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr<Err = String>,
+{
+    Ok(Option::<StringOrStruct<T>>::deserialize(deserializer)?.map(|entry| entry.0))
+}
+
+fn list_of_string_or_struct<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr<Err = String>,
+{
+    Ok(Option::<Vec<StringOrStruct<T>>>::deserialize(deserializer)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| entry.0)
+        .collect())
+}
+
+#[test]
+fn parse_short_and_long_syntax() -> Result<(), Box<dyn std::error::Error>> {
+    let config: Config = serde_yaml::from_str(include_str!("../../examples/php-fpm-and-nginx/compose.yml"))?;
+    let nginx = &config.services["nginx"];
+
+    assert_eq!(nginx.depends_on["php"], ServiceDependsOn::default());
+    assert_eq!(nginx.ports[0].published.as_deref(), Some("8080"));
+    assert_eq!(nginx.ports[0].target, 80);
+    assert!(nginx.volumes[0].read_only);
+    assert_eq!(nginx.volumes[0].volume_type, "bind");
+    assert!(nginx.networks.contains_key("dev-cli-web"));
+    assert_eq!(
+        nginx.labels["traefik.http.routers.${COMPOSE_PROJECT_NAME}.rule"],
+        "Host(`${COMPOSE_PROJECT_NAME}.${TLD}`)"
+    );
+    assert_eq!(config.networks["dev-cli-web"].external, Some(true));
+
+    let config: Config = serde_yaml::from_str(
+        "services:\n  \
+           app:\n    \
+             build: ./docker\n    \
+             command: [yarn, run, dev]\n    \
+             environment: {DEBUG: 1, EMPTY: }\n    \
+             ports: [3000, '127.0.0.1:9000-9001:8000-8001/udp', {target: 53, published: 5353}, '8000-8010:80']\n    \
+             volumes: ['data:/var/lib/data', '/tmp', {type: tmpfs, target: /run}]\n  \
+           worker:\n    \
+             build: {dockerfile: docker/worker.Dockerfile}\n",
+    )?;
+    let app = &config.services["app"];
+
+    assert_eq!(app.build.as_ref().unwrap().context, "./docker");
+    assert_eq!(app.command, Some(ServiceCommand::List(vec![
+        String::from("yarn"), String::from("run"), String::from("dev"),
+    ])));
+    assert_eq!(app.environment["DEBUG"].as_deref(), Some("1"));
+    assert_eq!(app.environment["EMPTY"], None);
+    assert_eq!(app.ports.len(), 5);
+    assert_eq!(app.ports[0].published, None);
+    assert_eq!(app.ports[2].host_ip.as_deref(), Some("127.0.0.1"));
+    assert_eq!(app.ports[2].published.as_deref(), Some("9001"));
+    assert_eq!(app.ports[2].target, 8001);
+    assert_eq!(app.ports[3].published.as_deref(), Some("5353"));
+    assert_eq!(app.ports[4].published.as_deref(), Some("8000-8010"));
+    assert_eq!(app.ports[4].target, 80);
+    assert_eq!(app.volumes[0].volume_type, "volume");
+    assert_eq!(app.volumes[1].source, None);
+    assert_eq!(app.volumes[2].volume_type, "tmpfs");
+    assert_eq!(config.services["worker"].build.as_ref().unwrap().context, ".");
+
+    Ok(())
+}
+
+#[test]
+fn select_services_to_restart() {
+    let config: Config = serde_yaml::from_str("services:\n  php: {image: php}\n  nginx: {image: nginx}\n").unwrap();
+
+    assert_eq!(config.select_services(&[]), Ok(None));
+    assert_eq!(config.select_services(&[String::from("php")]), Ok(Some(vec!["php"])));
+    assert!(config.select_services(&[String::from("php"), String::from("db")]).is_err());
 }
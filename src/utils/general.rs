@@ -1,18 +1,28 @@
 use anyhow::Result;
 use bollard::Docker;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use std::{collections::HashMap, env, path::Path};
 use bollard::network::{CreateNetworkOptions, ListNetworksOptions};
 use rust_embed::Embed;
 
 use crate::{CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT};
 
-use super::{app_config::AppConfig, path::find_recursively};
+use super::{app_config::AppConfig, database::Compression, path::find_recursively};
+
+/// The network shared by the proxy stack and all projects
+pub const DEV_CLI_NETWORK: &str = "dev-cli-web";
 
 #[derive(Embed)]
 #[folder = "files/"]
 pub struct Asset;
 
+/// Project templates for `dev-cli init`, one directory per template. The files in `files/init`
+/// are added to those a template doesn't have.
+#[derive(Embed)]
+#[folder = "examples/"]
+pub struct Template;
+
 #[derive(Debug, Parser)]
 #[command(version, about = "A CLI for managing local Docker development environments", long_about = None)]
 pub struct Cli {
@@ -33,7 +43,27 @@ pub struct Cli {
 #[derive(Debug, Clone, Subcommand, PartialEq)]
 pub enum Commands {
     /// Initialize a new project for dev-cli using pre-defined templates
-    Init,
+    Init {
+        /// The template to use, see --list
+        #[arg(default_value("static"))]
+        template: String,
+
+        /// The project name, defaults to the name of the current directory
+        #[arg(long)]
+        name: Option<String>,
+
+        /// The top level domain the project is served at
+        #[arg(long, default_value("test"))]
+        tld: String,
+
+        /// Overwrite existing files
+        #[arg(long, default_value("false"))]
+        force: bool,
+
+        /// List the available templates
+        #[arg(long, default_value("false"))]
+        list: bool,
+    },
     /// Starts a docker compose project
     Start,
     /// Stop and remove the containers of a project. Does not lose or harm anything unless you add --remove-data.
@@ -42,9 +72,23 @@ pub enum Commands {
         remove_data: bool,
     },
     /// Stops, removes and starts a project again
-    Restart,
+    Restart {
+        #[arg(long, default_value("false"))]
+        remove_data: bool,
+
+        /// Rebuild the images before starting
+        #[arg(long, default_value("false"))]
+        rebuild: bool,
+
+        /// Only restart these services
+        services: Vec<String>,
+    },
     /// Stop all projects and dev-cli containers (Traefik, etc.)
-    Poweroff,
+    Poweroff {
+        /// Stop the projects at the same time instead of one after the other
+        #[arg(long, default_value("false"))]
+        parallel: bool,
+    },
     /// Execute a shell command in the container for a service.
     Exec {
         #[arg(short, long)]
@@ -60,78 +104,371 @@ pub enum Commands {
         command: Vec<String>,
     },
     /// Starts a shell session in the container for a service
-    Shell,
+    Shell {
+        #[arg(short, long)]
+        service: Option<String>,
+
+        #[arg(short, long)]
+        user: Option<String>,
+    },
     /// Launches the default URL in the default browser
-    Launch,
+    Launch {
+        /// The service to open, required if more than one service has a route
+        service: Option<String>,
+
+        /// Only print the URL
+        #[arg(long, default_value("false"))]
+        print: bool,
+    },
     /// Show the status of the containers of this project
-    Status,
+    Status {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Remove all project information (including database) for an existing project
+    Delete {
+        /// Don't ask for confirmation
+        #[arg(short, long, default_value("false"))]
+        yes: bool,
+
+        /// Create a snapshot of the database before deleting it
+        #[arg(long, default_value("false"))]
+        snapshot: bool,
+    },
+    /// Get a detailed description of the project, its services and database
+    Describe {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// List the known projects with their URL and state, including those found in the configured workspaces
+    List {
+        /// Only list running projects
+        #[arg(long, default_value("false"), conflicts_with("stale"))]
+        running: bool,
+
+        /// Only list projects whose root is gone or which were not started for 30 days
+        #[arg(long, default_value("false"))]
+        stale: bool,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
     /// Show the status of all projects that ran through dev-cli
-    GlobalStatus,
-
-
-    // Removes items dev-cli has created
-    //Clean,
-    // Generate the autocompletion script for the specified shell
-    //Completion,
-    // Create or modify a dev-cli project configuration in the current directory
-    //Config,
-    // Remove all project information (including database) for an existing project
-    //Delete,
-    // Get a detailed description of a running dev-cli project
-    //Describe,
-    // Dump a database to a file or to stdout
-    //ExportDb,
+    GlobalStatus {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Get the logs from your running services.
+    Logs {
+        /// Only show the logs of these services
+        services: Vec<String>,
+
+        /// Keep streaming new log output
+        #[arg(short, long, default_value("false"))]
+        follow: bool,
+
+        /// Only show logs since a timestamp (RFC 3339 or unix) or a relative time (e.g. 30s, 10m, 2h, 1d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Number of lines to show from the end of the logs of each container
+        #[arg(short = 'n', long, default_value("all"))]
+        tail: String,
+
+        /// Only show lines containing this text
+        #[arg(long)]
+        grep: Option<String>,
+    },
+    /// Dump the database of the project to a file or to stdout
+    ExportDb {
+        /// The database to dump, defaults to the one created by the container
+        #[arg(long)]
+        database: Option<String>,
+
+        /// The file to write, `-` for stdout. Defaults to a timestamped file in the dumps directory
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Compress the dump, detected from the extension of --output if omitted
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+    },
+    /// Import a SQL dump into the database of the project, plain or compressed with gzip or zstd
+    ImportDb {
+        /// The dump to import, `-` for stdin. Defaults to the newest dump in the dumps directory
+        file: Option<String>,
+
+        /// The database to import into, defaults to the one created by the container
+        #[arg(long)]
+        database: Option<String>,
+
+        /// Drop and recreate the database before importing
+        #[arg(long, default_value("false"))]
+        recreate: bool,
+    },
+    /// Copy uploaded files from a directory or a .tar, .tar.gz or .zip archive into an upload directory of the project
+    ImportFiles {
+        source: String,
+
+        /// The upload directory by its path in the container, defaults to the first of `upload_dirs`
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Delete the files in the upload directory which are not in the source
+        #[arg(long, default_value("false"))]
+        delete: bool,
+    },
+    /// Generate the autocompletion script for the specified shell
+    Completion {
+        shell: Shell,
+    },
+    /// Prints the services or run recipes of the project, used by the completion scripts
+    #[command(hide = true)]
+    CompleteValues {
+        kind: CompletionValues,
+    },
+    /// Show, get, set and validate the dev-cli configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Create, list, restore and delete database snapshots of one or more projects
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Manage the hostnames of the project in the DNS container or the hosts file
+    Hostname {
+        #[command(subcommand)]
+        command: HostnameCommand,
+    },
+    /// Trust, renew and list the TLS certificates of the local CA
+    Cert {
+        #[command(subcommand)]
+        command: CertCommand,
+    },
+    /// Enable or disable extra services like a mail catcher or Redis from the add-on catalogue
+    Service {
+        #[command(subcommand)]
+        command: AddonCommand,
+    },
+    /// Removes items dev-cli has created, lists them with their size without arguments
+    Clean {
+        /// The kinds of items to remove
+        items: Vec<CleanItem>,
+
+        /// Remove everything
+        #[arg(long, default_value("false"), conflicts_with("items"))]
+        all: bool,
+
+        /// Don't ask for confirmation
+        #[arg(short, long, default_value("false"))]
+        yes: bool,
+    },
+
+
     // Get/Download a 3rd party add-on (service, provider, etc.)
     //Get,
-    // Manage your hostfile entries.
-    //Hostname,
-    // Import a SQL dump file into the project
-    //ImportDb,
-    // Pull the uploaded files directory of an existing project to the default public upload directory of your project
-    //ImportFiles,
-    // List projects
-    //List,
-    // Get the logs from your running services.
-    //Logs,
-    // Add or remove, enable or disable extra services
-    //Service,
-    // Create a database snapshot for one or more projects.
-    //Snapshot,
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum SnapshotCommand {
+    /// Create a snapshot of the database
+    Create {
+        /// Defaults to the current date and time
+        name: Option<String>,
+
+        /// Create a snapshot of every project that ran through dev-cli
+        #[arg(long, default_value("false"))]
+        all: bool,
+    },
+    /// List the snapshots of the project
+    List {
+        /// List the snapshots of every project that ran through dev-cli
+        #[arg(long, default_value("false"))]
+        all: bool,
+
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Replace the database with a snapshot
+    Restore {
+        /// Defaults to the newest snapshot
+        name: Option<String>,
+
+        /// Restore even if the snapshot is from another database flavour, a newer version or an unknown one
+        #[arg(long, default_value("false"))]
+        force: bool,
+    },
+    /// Delete a snapshot
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum AddonCommand {
+    /// Add an add-on to the project and start it if the project is running. The local config gets
+    /// its own list of add-ons, which replaces the one of the shared config from then on.
+    Enable {
+        addon: String,
+
+        /// Enable it in the shared config of the project (.dev-cli.dist.yml) instead of the local one
+        #[arg(long, default_value("false"))]
+        project: bool,
+    },
+    /// Stop an add-on and remove it from the project, its volumes are kept
+    Disable {
+        addon: String,
+
+        /// Disable it in the shared config of the project (.dev-cli.dist.yml) instead of the local one
+        #[arg(long, default_value("false"))]
+        project: bool,
+    },
+    /// List the add-ons of the catalogue and whether they are enabled
+    List,
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum HostnameCommand {
+    /// Register hostnames of the project, by default those of its Traefik routers
+    Add {
+        hosts: Vec<String>,
+    },
+    /// Unregister hostnames of the project, by default all of them
+    Remove {
+        hosts: Vec<String>,
+    },
+    /// List the hostnames of the project and where they are registered
+    List,
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum CertCommand {
+    /// Add the local CA to the trust store of the system
+    Trust,
+    /// Issue the certificate of the project again
+    Renew {
+        /// Issue every certificate again, e.g. after the CA was replaced
+        #[arg(long, default_value("false"))]
+        all: bool,
+    },
+    /// List the issued certificates and when they expire
+    List {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand, PartialEq)]
+pub enum ConfigCommand {
+    /// Show the merged config and which file each value comes from
+    Show,
+    /// Print a value of the merged config, e.g. `services.php.shell`
+    Get {
+        key: String,
+    },
+    /// Set a value in a config file, the local one (.dev-cli.yml) by default
+    Set {
+        key: String,
+
+        value: String,
+
+        /// Set it in the global config of the user
+        #[arg(long, group = "layer", default_value("false"))]
+        global: bool,
+
+        /// Set it in the shared config of the project (.dev-cli.dist.yml)
+        #[arg(long, group = "layer", default_value("false"))]
+        project: bool,
+
+        /// Set it in the local config of the project (.dev-cli.yml)
+        #[arg(long, group = "layer", default_value("false"))]
+        local: bool,
+    },
+    /// Check the config files for errors and unknown keys
+    Validate,
+}
+
+/// The kinds of items `clean` can remove
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CleanItem {
+    /// The extracted proxy stack (Traefik, DNS) and its containers
+    Proxy,
+    /// The local CA, the certificates it issued and their Traefik config
+    Certs,
+    /// DNS snippets of the projects
+    Dns,
+    /// Volumes of registered projects whose directory no longer exists
+    Volumes,
+    /// The generated compose files of the add-ons, written again by the next command of a project
+    Addons,
+    /// The shared `dev-cli-web` network
+    Network,
+}
+
+impl std::fmt::Display for CleanItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// The dynamic values of the completion scripts
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CompletionValues {
+    Services,
+    Recipes,
+}
+
+/// How commands print their results, `json` and `yaml` are meant for scripts
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
 }
 
 impl Commands {
     pub fn requires_docker(&self) -> bool {
-        match self {
+        matches!(
+            self,
             Commands::Start
-            | Commands::Stop { .. }
-            | Commands::Restart
-            | Commands::Poweroff
-            | Commands::Exec { .. }
-            | Commands::Run { .. }
-            | Commands::Shell
-            | Commands::Status
-            | Commands::GlobalStatus => true,
-            _ => false
-        }
+                | Commands::Stop { .. }
+                | Commands::Restart { .. }
+                | Commands::Poweroff { .. }
+                | Commands::Exec { .. }
+                | Commands::Run { .. }
+                | Commands::Shell { .. }
+                | Commands::Status { .. }
+                | Commands::Describe { .. }
+                | Commands::Delete { .. }
+                | Commands::GlobalStatus { .. }
+                | Commands::List { .. }
+                | Commands::Logs { .. }
+                | Commands::ExportDb { .. }
+                | Commands::ImportDb { .. }
+                | Commands::ImportFiles { .. }
+                | Commands::Snapshot { .. }
+        )
     }
 }
 
 pub fn is_docker_required(
     command: &Option<Commands>,
-    exec_command: &Vec<String>,
+    exec_command: &[String],
 ) -> bool {
     let required_by_command = match command {
         Some(command) => command.requires_docker(),
         None => false,
     };
-    required_by_command || exec_command.len() > 0
+    required_by_command || !exec_command.is_empty()
 }
 
 pub async fn docker_running(docker: &Docker) -> String {
     match docker.ping().await {
         Ok(result) => result,
         Err(error) => {
-            println!("Docker doesn't seem to be turned on ({})", error);
+            eprintln!("Docker doesn't seem to be turned on ({})", error);
             sysexits::ExitCode::OsErr.exit()
             //Err(anyhow::anyhow!("Docker doesn't seem to be turned on ({})", error))
         }
@@ -224,10 +561,12 @@ pub fn check_and_setup_system() {
     //}
 }
 
+/// Creates the shared network if it's missing. Messages go to stderr, as commands like
+/// `export-db --output -` write their result to stdout.
 pub async fn check_and_setup_docker(docker: &bollard::Docker) {
     // Check that the docker network "dev-cli-web" exists using bollard
     let mut list_networks_filters = HashMap::new();
-    list_networks_filters.insert("name", vec!["dev-cli-web"]);
+    list_networks_filters.insert("name", vec![DEV_CLI_NETWORK]);
     let config = ListNetworksOptions {
         filters: list_networks_filters,
     };
@@ -236,30 +575,43 @@ pub async fn check_and_setup_docker(docker: &bollard::Docker) {
     match networks {
         Ok(networks) => {
             if networks.is_empty() {
-                println!("Creating the network 'dev-cli-web'...");
+                eprintln!("Creating the network 'dev-cli-web'...");
                 let config = CreateNetworkOptions {
-                    name: "dev-cli-web",
+                    name: DEV_CLI_NETWORK,
                     ..Default::default()
                 };
 
                 let network_created = docker.create_network(config).await;
                 match network_created {
-                    Ok(_) => println!("Network 'dev-cli-web' created successfully"),
+                    Ok(_) => eprintln!("Network 'dev-cli-web' created successfully"),
                     Err(error) => {
-                        println!("Could not create the network 'dev-cli-web': {}", error);
+                        eprintln!("Could not create the network 'dev-cli-web': {}", error);
                         sysexits::ExitCode::OsErr.exit()
                     }
                 }
             }
         }
         Err(error) => {
-            println!("Could not list networks: {}", error);
+            eprintln!("Could not list networks: {}", error);
             sysexits::ExitCode::OsErr.exit()
         }
     }
 }
 
 pub fn get_project_root() -> Result<Box<Path>> {
+    match find_project_root()? {
+        Some(project_root) => Ok(project_root),
+        None => {
+            eprintln!("Could not find a project root. Please add a {} or {} to your project root",
+                      CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT
+            );
+            std::process::exit(sysexits::ExitCode::OsErr as i32)
+        }
+    }
+}
+
+/// Like `get_project_root`, for commands which also work outside of a project
+pub fn find_project_root() -> Result<Option<Box<Path>>> {
     let cwd = env::current_dir()?;
 
     let local_config = find_recursively(&cwd, CONFIG_FILE_NAME_LOCAL);
@@ -268,34 +620,110 @@ pub fn get_project_root() -> Result<Box<Path>> {
     let project_root = match (local_config.as_ref(), project_config.as_ref()) {
         (Some(filepath), _) => filepath.parent().unwrap(),
         (_, Some(filepath)) => filepath.parent().unwrap(),
-        (None, None) => {
-            eprintln!("Could not find a project root. Please add a {} or {} to your project root",
-                      CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT
-            );
-            std::process::exit(sysexits::ExitCode::OsErr as i32)
-        }
+        (None, None) => return Ok(None),
     };
 
-    Ok(Box::from(project_root))
+    Ok(Some(Box::from(project_root)))
 }
 
 pub fn get_app_config(project_root: &Path) -> Result<AppConfig> {
-    AppConfig::merge_from_project_root(&project_root)
+    AppConfig::merge_from_project_root(project_root)
 }
 
-pub fn ensure_proxy_running() -> Result<(), Box<dyn std::error::Error>> {
-    for file in Asset::get("docker/")::iter() {
-        println!("{}", file.as_ref());
+/// Opens a URL or file with the default application of the platform
+pub fn open_with_default_app(target: &str) -> Result<()> {
+    let cmd = if cfg!(target_os = "macos") {
+        subprocess::Exec::cmd("open").arg(target)
+    } else if cfg!(target_os = "windows") {
+        subprocess::Exec::cmd("cmd").args(&["/C", "start", "", target])
+    } else {
+        subprocess::Exec::cmd("xdg-open").arg(target)
+    };
+
+    let status = cmd.join()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Could not open {} ({:?})", target, status));
     }
-    for file in Asset::iter() {
-        println!("{}", file.as_ref());
+    Ok(())
+}
+
+/// Prints rows as columns aligned to the widest cell
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            widths[index] = widths[index].max(cell.chars().count());
+        }
     }
-    let dot_env = Asset::get("docker/.env");
-    if dot_env == None {
-        sysexits::ExitCode::OsErr.exit()
+
+    let print_row = |cells: Vec<&str>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(headers.to_vec());
+    for row in rows {
+        print_row(row.iter().map(String::as_str).collect());
     }
+}
 
-    println!("{:?}", std::str::from_utf8(dot_env.data.as_ref()));
+/// Formats a duration with its two most significant units, e.g. `2d 4h` or `5m 12s`
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let parts = [
+        (seconds / 86400, "d"),
+        (seconds % 86400 / 3600, "h"),
+        (seconds % 3600 / 60, "m"),
+        (seconds % 60, "s"),
+    ];
 
-    Ok(())
+    let parts: Vec<String> = parts
+        .iter()
+        .skip_while(|(value, _)| *value == 0)
+        .take(2)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        String::from("0s")
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `12.3 MiB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Asks a yes/no question on the terminal, `no` is the default
+pub fn confirm(question: &str) -> Result<bool> {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!("Can't ask for confirmation without a terminal, use --yes to skip it"));
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
 }
@@ -1,4 +1,13 @@
 pub mod general;
+pub mod addons;
 pub mod app_config;
 pub mod docker_compose;
+pub mod certs;
+pub mod compose_loader;
+pub mod dns;
+pub mod database;
 pub mod path;
+pub mod project_registry;
+pub mod proxy;
+pub mod traefik;
+pub mod yaml_edit;
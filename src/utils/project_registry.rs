@@ -0,0 +1,151 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{CONFIG_FILE_NAME_LOCAL, CONFIG_FILE_NAME_PROJECT, PROJECT_REGISTRY_PATH};
+
+/// A project which ran through dev-cli
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisteredProject {
+    pub root: PathBuf,
+    /// The compose project name, used to find the containers of the project
+    pub name: String,
+    pub last_used: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectRegistry {
+    #[serde(default)]
+    pub projects: Vec<RegisteredProject>,
+}
+
+impl ProjectRegistry {
+    pub fn load() -> Result<Self> {
+        Self::load_from(&PROJECT_REGISTRY_PATH)
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let registry: Option<ProjectRegistry> = serde_yaml::from_reader(File::open(path)?)
+            .with_context(|| format!("Could not parse {}", path.display()))?;
+        Ok(registry.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&PROJECT_REGISTRY_PATH)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        serde_yaml::to_writer(File::create(path)?, self)
+            .with_context(|| format!("Could not write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Adds the project, or updates its name and last used time if it is already known
+    pub fn record(&mut self, root: &Path, name: &str) {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        self.projects.retain(|project| project.root != root);
+        self.projects.push(RegisteredProject {
+            root,
+            name: name.to_string(),
+            last_used: Utc::now(),
+        });
+        self.projects.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Forgets the project, returns whether it was known
+    pub fn remove(&mut self, root: &Path) -> bool {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let count = self.projects.len();
+        self.projects.retain(|project| project.root != root);
+        self.projects.len() != count
+    }
+}
+
+/// Records that the project was started now, `list --stale` and `global-status` go by this time
+pub fn record_started(root: &Path, name: &str) -> Result<()> {
+    let mut registry = ProjectRegistry::load()?;
+    registry.record(root, name);
+    registry.save()
+}
+
+/// Finds project roots, directories with a dev-cli config, in and up to `depth` levels below the
+/// workspace directories. Hidden directories are skipped and nothing is searched below a project.
+pub fn scan_workspaces(workspaces: &[PathBuf], depth: usize) -> Vec<PathBuf> {
+    fn scan(dir: &Path, depth: usize, roots: &mut Vec<PathBuf>) {
+        if dir.join(CONFIG_FILE_NAME_LOCAL).is_file() || dir.join(CONFIG_FILE_NAME_PROJECT).is_file() {
+            roots.push(dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()));
+            return;
+        }
+        if depth == 0 {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .map(|entry| entry.path())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            scan(&dir, depth - 1, roots);
+        }
+    }
+
+    let mut roots = vec![];
+    for workspace in workspaces {
+        scan(workspace, depth, &mut roots);
+    }
+    roots.dedup();
+    roots
+}
+
+#[test]
+fn record_project_once_per_root() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = assert_fs::TempDir::new()?;
+    let registry_path = temp_dir.path().join("dev-cli").join("projects.yml");
+
+    let mut registry = ProjectRegistry::load_from(&registry_path)?;
+    registry.record(temp_dir.path(), "first");
+    registry.record(temp_dir.path(), "renamed");
+    registry.save_to(&registry_path)?;
+
+    let registry = ProjectRegistry::load_from(&registry_path)?;
+    assert_eq!(registry.projects.len(), 1);
+    assert_eq!(registry.projects[0].name, "renamed");
+
+    let mut registry = registry;
+    assert!(registry.remove(temp_dir.path()));
+    assert!(registry.projects.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn scan_workspaces_for_projects() -> Result<(), Box<dyn std::error::Error>> {
+    use assert_fs::prelude::*;
+
+    let workspace = assert_fs::TempDir::new()?;
+    workspace.child("shop/.dev-cli.dist.yml").touch()?;
+    workspace.child("shop/packages/theme/.dev-cli.yml").touch()?;
+    workspace.child("clients/blog/.dev-cli.yml").touch()?;
+    workspace.child("clients/archive/2019/old/.dev-cli.yml").touch()?;
+    workspace.child(".cache/tool/.dev-cli.yml").touch()?;
+
+    let roots = scan_workspaces(&[workspace.path().to_path_buf()], 2);
+    let workspace = workspace.path().canonicalize()?;
+    assert_eq!(roots, vec![workspace.join("clients/blog"), workspace.join("shop")]);
+
+    Ok(())
+}
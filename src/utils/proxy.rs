@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use bollard::container::InspectContainerOptions;
+use bollard::models::HealthStatusEnum;
+use bollard::Docker;
+
+use crate::DATA_DIR;
+use super::certs;
+use super::compose_loader;
+use super::docker_compose::DockerCompose;
+use super::general::Asset;
+
+/// The container name of Traefik in `files/docker/compose.yml`
+pub const TRAEFIK_CONTAINER: &str = "traefik";
+
+/// Written next to the extracted files, to know whether the embedded files changed since
+const ASSETS_MANIFEST: &str = ".assets";
+
+/// Directories mounted into the proxy stack, relative to the data directory
+const PROXY_DIRS: [&str; 3] = ["certs", "certs-conf", "dns"];
+
+/// Where the compose project of the proxy stack is extracted to
+pub fn proxy_dir() -> PathBuf {
+    DATA_DIR.join("docker")
+}
+
+/// The compose project name of the proxy stack, as set in the embedded `.env`
+pub fn project_name() -> String {
+    Asset::get("docker/.env")
+        .and_then(|dot_env| compose_loader::parse_env(&String::from_utf8_lossy(&dot_env.data)).ok())
+        .and_then(|variables| variables.get("COMPOSE_PROJECT_NAME").cloned())
+        .unwrap_or_else(|| String::from("dev-cli"))
+}
+
+/// The files created for a single project in the proxy directories. They are named after the
+/// project, like `dns/shop.conf` or `certs/shop.key.pem`.
+pub fn project_files(project: &str) -> Vec<PathBuf> {
+    project_files_in(&DATA_DIR, project)
+}
+
+fn project_files_in(data_dir: &Path, project: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = PROXY_DIRS
+        .iter()
+        .filter_map(|dir| fs::read_dir(data_dir.join(dir)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('.').next())
+                == Some(project)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+pub fn docker_compose() -> DockerCompose {
+    DockerCompose::new(proxy_dir().join("compose.yml"))
+}
+
+/// Extracts the proxy stack (Traefik and DNS) and starts it, unless it is already running. The DNS
+/// container is left out if `dns_container` is disabled in the config.
+pub async fn ensure_proxy_running(docker: &Docker, dns_container: bool) -> Result<()> {
+    let extracted = extract_assets()?;
+    certs::ensure_default_certificate()?;
+    let docker_compose = docker_compose();
+
+    if !extracted && is_traefik_healthy(docker).await {
+        return Ok(());
+    }
+
+    if extracted {
+        // Running containers don't pick up changed config files on their own
+        docker_compose.down(None, false).map_err(|error| anyhow!("{}", error))?;
+    }
+    println!("Starting the dev-cli proxy ...");
+    let services = if dns_container { None } else { Some(vec![TRAEFIK_CONTAINER]) };
+    docker_compose.up(services, true).map_err(|error| anyhow!("{}", error))?;
+
+    wait_for_traefik(docker, Duration::from_secs(60)).await
+}
+
+/// Writes the embedded `files/docker/` to the data directory if they changed since the last time
+fn extract_assets() -> Result<bool> {
+    // `clean` may have removed them while the stack itself is unchanged
+    for dir in PROXY_DIRS {
+        fs::create_dir_all(DATA_DIR.join(dir))?;
+    }
+
+    let manifest = assets_manifest();
+    let manifest_path = proxy_dir().join(ASSETS_MANIFEST);
+    if fs::read_to_string(&manifest_path).ok().as_deref() == Some(manifest.as_str()) {
+        return Ok(false);
+    }
+
+    for file in Asset::iter().filter(|file| file.starts_with("docker/")) {
+        let asset = Asset::get(&file).unwrap();
+        let path = DATA_DIR.join(file.as_ref());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, asset.data)?;
+    }
+    fs::write(manifest_path, manifest)?;
+
+    Ok(true)
+}
+
+/// Lists the hash of every embedded file of the proxy stack
+fn assets_manifest() -> String {
+    let mut files: Vec<String> = Asset::iter()
+        .filter(|file| file.starts_with("docker/"))
+        .map(|file| file.to_string())
+        .collect();
+    files.sort();
+
+    files
+        .iter()
+        .map(|file| {
+            let hash: String = Asset::get(file)
+                .unwrap()
+                .metadata
+                .sha256_hash()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            format!("{}  {}\n", hash, file)
+        })
+        .collect()
+}
+
+async fn is_traefik_healthy(docker: &Docker) -> bool {
+    let state = match docker.inspect_container(TRAEFIK_CONTAINER, None::<InspectContainerOptions>).await {
+        Ok(container) => container.state,
+        Err(_) => return false,
+    };
+    state
+        .and_then(|state| state.health)
+        .and_then(|health| health.status)
+        == Some(HealthStatusEnum::HEALTHY)
+}
+
+/// Traefik reports healthy once its `ping` endpoint responds, see the healthcheck in `files/docker/compose.yml`
+async fn wait_for_traefik(docker: &Docker, timeout: Duration) -> Result<()> {
+    let started = std::time::Instant::now();
+    while started.elapsed() < timeout {
+        if is_traefik_healthy(docker).await {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Err(anyhow!("Traefik did not become healthy within {} seconds", timeout.as_secs()))
+}
+
+#[test]
+fn find_the_files_of_a_project() -> Result<(), Box<dyn std::error::Error>> {
+    use assert_fs::prelude::*;
+
+    let data_dir = assert_fs::TempDir::new()?;
+    for file in ["dns/shop.conf", "dns/shop-admin.conf", "certs/shop.pem", "certs/shop.key.pem", "certs-conf/shop.yml", "certs/dev-cli-default.pem", "docker/shop.yml"] {
+        data_dir.child(file).touch()?;
+    }
+
+    let files: Vec<PathBuf> = project_files_in(data_dir.path(), "shop")
+        .iter()
+        .map(|file| file.strip_prefix(data_dir.path()).unwrap().to_path_buf())
+        .collect();
+    assert_eq!(files, [
+        PathBuf::from("certs/shop.key.pem"),
+        PathBuf::from("certs/shop.pem"),
+        PathBuf::from("certs-conf/shop.yml"),
+        PathBuf::from("dns/shop.conf"),
+    ]);
+    Ok(())
+}
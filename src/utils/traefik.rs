@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use super::docker_compose::Config;
+
+/// A Traefik HTTP router defined through the labels of a service
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Router {
+    pub name: String,
+    pub rule: String,
+    pub tls: bool,
+    pub hosts: Vec<String>,
+}
+
+/// Collects the routers from labels like `traefik.http.routers.<name>.rule=Host(`example.test`)`
+pub fn routers<'a>(labels: impl IntoIterator<Item = (&'a String, &'a String)>) -> Vec<Router> {
+    let labels: Vec<(&String, &String)> = labels.into_iter().collect();
+
+    let mut routers: Vec<Router> = labels
+        .iter()
+        .filter_map(|(key, rule)| {
+            let name = key
+                .strip_prefix("traefik.http.routers.")?
+                .strip_suffix(".rule")?;
+            let tls_prefix = format!("traefik.http.routers.{}.tls", name);
+            let tls = labels.iter().any(|(key, value)| {
+                (**key == tls_prefix && value.as_str() == "true")
+                    || key.starts_with(&format!("{}.", tls_prefix))
+            });
+
+            Some(Router {
+                name: name.to_string(),
+                rule: rule.to_string(),
+                tls,
+                hosts: hosts_from_rule(rule),
+            })
+        })
+        .collect();
+
+    routers.sort_by(|a, b| a.name.cmp(&b.name));
+    routers
+}
+
+/// The routers of every service of a project which has at least one
+pub fn service_routers(config: &Config) -> BTreeMap<String, Vec<Router>> {
+    config.services
+        .iter()
+        .map(|(name, service)| (name.clone(), routers(&service.labels)))
+        .filter(|(_, routers)| !routers.is_empty())
+        .collect()
+}
+
+/// The URL of the first router with a hostname, routers with TLS come first
+pub fn primary_url(routers: &[Router]) -> Option<String> {
+    let router = routers
+        .iter()
+        .filter(|router| !router.hosts.is_empty())
+        .find(|router| router.tls)
+        .or_else(|| routers.iter().find(|router| !router.hosts.is_empty()))?;
+
+    let scheme = if router.tls { "https" } else { "http" };
+    Some(format!("{}://{}", scheme, router.hosts[0]))
+}
+
+/// Extracts the hostnames of all `Host(...)` matchers of a rule
+pub fn hosts_from_rule(rule: &str) -> Vec<String> {
+    let mut hosts = vec![];
+    let mut rest = rule;
+
+    while let Some(start) = rest.find("Host(") {
+        let arguments = &rest[start + "Host(".len()..];
+        let end = arguments.find(')').unwrap_or(arguments.len());
+        for host in arguments[..end].split(',') {
+            let host = host.trim().trim_matches(|char| char == '`' || char == '"');
+            if !host.is_empty() {
+                hosts.push(host.to_string());
+            }
+        }
+        rest = &arguments[end..];
+    }
+
+    hosts
+}
+
+#[test]
+fn routers_from_labels() {
+    let labels = std::collections::BTreeMap::from([
+        (String::from("traefik.http.routers.web0.rule"), String::from("Host(`web.test`)")),
+        (String::from("traefik.http.routers.web0.middlewares"), String::from("redirect-to-https")),
+        (String::from("traefik.http.routers.web.rule"), String::from("Host(`web.test`, `www.web.test`) || PathPrefix(`/api`)")),
+        (String::from("traefik.http.routers.web.tls"), String::from("true")),
+    ]);
+
+    let routers = routers(&labels);
+
+    assert_eq!(routers.len(), 2);
+    assert_eq!(routers[0].name, "web");
+    assert!(routers[0].tls);
+    assert_eq!(routers[0].hosts, vec!["web.test", "www.web.test"]);
+    assert!(!routers[1].tls);
+    assert_eq!(primary_url(&routers).as_deref(), Some("https://web.test"));
+}
@@ -0,0 +1,180 @@
+use anyhow::{bail, Result};
+use serde_yaml::{Mapping, Value};
+
+/// Sets the value at `path` in a YAML document by editing its lines, so comments and the formatting
+/// of everything else stay as they are. Only block mappings are followed, which is what config files use,
+/// an inline mapping on the way is rewritten as a block with the same entries.
+pub fn set_value(content: &str, path: &[&str], value: &str) -> Result<String> {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut parent_indent: Option<usize> = None;
+
+    for (depth, key) in path.iter().enumerate() {
+        let block_indent = (start..end)
+            .filter(|&index| is_content(&lines[index]))
+            .map(|index| indentation(&lines[index]))
+            .next();
+        let found = (start..end).find(|&index| {
+            Some(indentation(&lines[index])) == block_indent && key_of(&lines[index]) == Some(*key)
+        });
+
+        let Some(index) = found else {
+            // Everything from here on is new, it goes after the last line of the block
+            let indent = block_indent.unwrap_or_else(|| parent_indent.map_or(0, |indent| indent + 2));
+            let insert_at = (start..end)
+                .rev()
+                .find(|&index| is_content(&lines[index]))
+                .map_or(start, |index| index + 1);
+            let new_lines = path[depth..].iter().enumerate().map(|(offset, key)| {
+                let padding = " ".repeat(indent + offset * 2);
+                if depth + offset == path.len() - 1 {
+                    format!("{}{}: {}", padding, key, value)
+                } else {
+                    format!("{}{}:", padding, key)
+                }
+            });
+            lines.splice(insert_at..insert_at, new_lines);
+            break;
+        };
+
+        let indent = indentation(&lines[index]);
+        let block_end = (index + 1..end)
+            .find(|&line| is_content(&lines[line]) && indentation(&lines[line]) <= indent)
+            .unwrap_or(end);
+        let comment = comment_of(&lines[index]);
+
+        if depth == path.len() - 1 {
+            // A nested block is replaced by the new value
+            let children_end = (index + 1..block_end)
+                .rev()
+                .find(|&line| is_content(&lines[line]))
+                .map_or(index + 1, |line| line + 1);
+            lines.splice(index..children_end, [format!("{}{}: {}{}", " ".repeat(indent), key, value, comment)]);
+            break;
+        }
+
+        // An inline value like `services: {}` makes room for the nested keys, without losing its entries
+        let inline = value_of(&lines[index]);
+        if !inline.is_empty() {
+            let entries = match serde_yaml::from_str::<Value>(inline) {
+                Ok(Value::Null) => Mapping::new(),
+                Ok(Value::Mapping(entries)) => entries,
+                _ => bail!("'{}' is not a mapping, so it can't have a '{}'", path[..=depth].join("."), path[depth + 1]),
+            };
+            let padding = " ".repeat(indent + 2);
+            let children: Vec<String> = if entries.is_empty() {
+                vec![]
+            } else {
+                serde_yaml::to_string(&entries)?.lines().map(|line| format!("{}{}", padding, line)).collect()
+            };
+            lines[index] = format!("{}{}:{}", " ".repeat(indent), key, comment);
+            end = block_end + children.len();
+            lines.splice(index + 1..index + 1, children);
+        } else {
+            end = block_end;
+        }
+        start = index + 1;
+        parent_indent = Some(indent);
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    Ok(content)
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---"
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// The key of a `key: value` line, without quotes
+fn key_of(line: &str) -> Option<&str> {
+    split_key(line).map(|(key, _)| key.trim().trim_matches(|char| char == '"' || char == '\''))
+}
+
+/// The value of a `key: value` line, without the trailing comment
+fn value_of(line: &str) -> &str {
+    let line = &line[..line.len() - comment_of(line).len().saturating_sub(1)];
+    split_key(line).map_or("", |(_, value)| value.trim())
+}
+
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with(['#', '-']) {
+        return None;
+    }
+    let end = trimmed
+        .match_indices(':')
+        .map(|(index, _)| index)
+        .find(|&index| trimmed[index + 1..].is_empty() || trimmed[index + 1..].starts_with([' ', '\t']))?;
+    Some((&trimmed[..end], &trimmed[end + 1..]))
+}
+
+/// The trailing comment of a line including the space before it, or an empty string
+fn comment_of(line: &str) -> String {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, char) in line.char_indices() {
+        match (quote, char) {
+            (None, '"' | '\'') => quote = Some(char),
+            (Some(open), _) if char == open => quote = None,
+            (None, '#') if previous.is_whitespace() => return format!(" {}", &line[index..]),
+            _ => {}
+        }
+        previous = char;
+    }
+    String::new()
+}
+
+#[test]
+fn set_value_keeps_comments() -> Result<()> {
+    let content = "# Project settings\n\
+                   dumps_dir: backups # shared with the team\n\
+                   \n\
+                   services:\n  \
+                     php:\n    \
+                       user: www-data\n\
+                   \n\
+                   # Recipes\n\
+                   run-commands: {}\n\
+                   hosts: {nginx: [shop], php: {user: www-data}} # routed by Traefik\n";
+
+    let content = set_value(content, &["dumps_dir"], "dumps")?;
+    let content = set_value(&content, &["services", "php", "shell"], "bash")?;
+    let content = set_value(&content, &["services", "node", "user"], "node")?;
+    let content = set_value(&content, &["run-commands", "dev", "parallel"], "true")?;
+    let content = set_value(&content, &["hosts", "php", "shell"], "zsh")?;
+    let content = set_value(&content, &["database_container"], "mysql")?;
+    assert!(set_value(&content, &["dumps_dir", "path"], "dumps").is_err());
+
+    assert_eq!(
+        content,
+        "# Project settings\n\
+         dumps_dir: dumps # shared with the team\n\
+         \n\
+         services:\n  \
+           php:\n    \
+             user: www-data\n    \
+             shell: bash\n  \
+           node:\n    \
+             user: node\n\
+         \n\
+         # Recipes\n\
+         run-commands:\n  \
+           dev:\n    \
+             parallel: true\n\
+         hosts: # routed by Traefik\n  \
+           nginx:\n  \
+           - shop\n  \
+           php:\n    \
+             user: www-data\n    \
+             shell: zsh\n\
+         database_container: mysql\n"
+    );
+    Ok(())
+}
@@ -1,5 +1,4 @@
 use std::env;
-use std::path::{Path, PathBuf};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 use std::process::Command;
@@ -27,4 +26,74 @@ fn config_golden_path() -> Result<(), Box<dyn std::error::Error>> {
         .success();
         // .stdout(predicate::str::contains("error"));
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn launch_print_prefers_tls_router() -> Result<(), Box<dyn std::error::Error>> {
+    let project_dir = assert_fs::TempDir::new()?;
+    project_dir.child(".dev-cli.dist.yml").touch()?;
+    project_dir.child(".env").write_str("COMPOSE_PROJECT_NAME=shop\nTLD=test\n")?;
+    project_dir.child("compose.yml").write_str(include_str!("../examples/static/compose.yml"))?;
+
+    let mut cmd = Command::cargo_bin("dev-cli")?;
+    cmd.current_dir(project_dir.path())
+        .env_remove("COMPOSE_PROJECT_NAME")
+        .env_remove("TLD")
+        .args(["launch", "--print"])
+        .assert()
+        .success()
+        .stdout("https://shop.test\n");
+    Ok(())
+}
+
+#[test]
+fn init_refuses_to_overwrite_without_force() -> Result<(), Box<dyn std::error::Error>> {
+    let project_dir = assert_fs::TempDir::new()?;
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project_dir.path())
+        .args(["init", "php-fpm-and-nginx", "--name", "shop", "--tld", "localhost"])
+        .assert()
+        .success();
+    project_dir.child("compose.yml").assert(predicate::path::is_file());
+    project_dir.child("src/index.php").assert(predicate::path::is_file());
+    project_dir.child(".env").assert("COMPOSE_PROJECT_NAME=shop\nTLD=localhost\n");
+
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project_dir.path())
+        .args(["init", "php-fpm-and-nginx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    Command::cargo_bin("dev-cli")?
+        .current_dir(project_dir.path())
+        .args(["init", "php-fpm-and-nginx", "--force"])
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn config_set_keeps_comments_and_refuses_unknown_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let project = assert_fs::TempDir::new()?;
+    project.child(".dev-cli.yml").write_str("# Personal settings\nservices:\n  php:\n    user: www-data # for file permissions\n")?;
+
+    let mut cmd = Command::cargo_bin("dev-cli")?;
+    cmd.current_dir(project.path()).args(["config", "set", "services.php.shell", "zsh"]);
+    cmd.assert().success();
+    project.child(".dev-cli.yml").assert(
+        "# Personal settings\nservices:\n  php:\n    user: www-data # for file permissions\n    shell: zsh\n",
+    );
+
+    let mut cmd = Command::cargo_bin("dev-cli")?;
+    cmd.current_dir(project.path()).args(["config", "get", "services.php.shell"]);
+    cmd.assert().success().stdout("zsh\n");
+
+    let mut cmd = Command::cargo_bin("dev-cli")?;
+    cmd.current_dir(project.path()).args(["config", "set", "services.php.shel", "zsh"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key 'services.php.shel'"));
+
+    Ok(())
+}